@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tmux_sessionizer::config::ScanOptions;
+
+const DIR_COUNT: usize = 5000;
+
+fn list(cache_dir: &Path, root: &Path) -> usize {
+    let mut count = 0;
+    tmux_sessionizer::cache::subdirs(cache_dir, root, &ScanOptions::default(), |_| {
+        count += 1;
+        Ok(true)
+    })
+    .unwrap();
+    count
+}
+
+fn listing(c: &mut Criterion) {
+    let root = tempfile::tempdir().unwrap();
+    for i in 0..DIR_COUNT {
+        fs::create_dir(root.path().join(format!("project-{i:05}"))).unwrap();
+    }
+    let cache = tempfile::tempdir().unwrap();
+
+    c.bench_function("cold listing", |b| {
+        b.iter(|| {
+            tmux_sessionizer::cache::clear(cache.path()).unwrap();
+            list(cache.path(), root.path())
+        })
+    });
+
+    list(cache.path(), root.path());
+    c.bench_function("warm listing", |b| b.iter(|| list(cache.path(), root.path())));
+}
+
+criterion_group!(benches, listing);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tmux_sessionizer::config::{self, Config, Finder};
+use tmux_sessionizer::run;
+use tmux_sessionizer::tmux::Tmux;
+
+fn tmux(socket: &Path, args: &[&str]) {
+    Command::new("tmux").arg("-S").arg(socket).args(args).output().unwrap();
+}
+
+fn config(dir: &Path, name: &str) -> Config {
+    Config {
+        command: Some(config::Command::Switch {
+            name: name.to_owned(),
+            grouped: false,
+            window: false,
+        }),
+        hide_banner: true,
+        sort: false,
+        default_dir: dir.to_path_buf().into(),
+        finder: Finder::Skim,
+        ..Default::default()
+    }
+}
+
+/// Time from the selection to the client showing the session, against a private server with a control mode client.
+fn switching(c: &mut Criterion) {
+    if Command::new("tmux").arg("-V").output().is_err() {
+        eprintln!("tmux is not installed, skipping");
+        return;
+    }
+    std::env::remove_var("TMUX");
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("tmux.sock");
+    tmux(&socket, &["-f", "/dev/null", "new-session", "-d", "-s", "base"]);
+    tmux(&socket, &["new-session", "-d", "-s", "other"]);
+    let mut client = Command::new("tmux")
+        .arg("-S")
+        .arg(&socket)
+        .args(["-C", "attach", "-t", "base"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    let switch = |name: &str| {
+        let config = config(dir.path(), name);
+        let tmux = Arc::new(Tmux::new().with_socket(&socket).with_inside_tmux(true));
+        run(Vec::new(), &tmux, &config, &config.finder).unwrap();
+    };
+
+    c.bench_function("switch to existing session", |b| {
+        let mut names = ["base", "other"].iter().cycle();
+        b.iter(|| switch(names.next().unwrap()))
+    });
+
+    c.bench_function("create and switch to new session", |b| {
+        b.iter_custom(|iters| {
+            let mut total = Duration::ZERO;
+            for i in 0..iters {
+                let name = format!("new-{i}");
+                let start = Instant::now();
+                switch(&name);
+                total += start.elapsed();
+                switch("base");
+                tmux(&socket, &["kill-session", "-t", &name]);
+            }
+            total
+        })
+    });
+
+    let _ = client.kill();
+    let _ = client.wait();
+    tmux(&socket, &["kill-server"]);
+}
+
+criterion_group!(benches, switching);
+criterion_main!(benches);
@@ -0,0 +1,251 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ScanOptions;
+use crate::error::{Context, Result};
+use crate::scan::IGNORE_FILES;
+
+/// Subdirectories of a scanned root together with what they were scanned with. Paths are stored as bytes as they are
+/// not necessarily valid UTF-8.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct CachedScan {
+    root: Vec<u8>,
+    options: (bool, bool, bool),
+    stamp: Vec<Option<(u64, u32)>>,
+    dirs: Vec<Vec<u8>>,
+}
+
+/// Lists the subdirectories of root like [`crate::scan::subdirs`], passing each one to found until it returns false.
+/// Cached dirs which still exist are passed right away, then the cache is revalidated like [`revalidate`] does.
+///
+/// Failing to read or write the cache is not an error, the dirs are then scanned as if nothing was cached.
+pub fn subdirs<F>(cache_dir: &Path, root: &Path, options: &ScanOptions, mut found: F) -> Result<()>
+where
+    F: FnMut(PathBuf) -> Result<bool>,
+{
+    let cached = cached(cache_dir, root, options).unwrap_or_default();
+    for dir in &cached {
+        if !found(dir.clone())? {
+            return Ok(());
+        }
+    }
+    revalidate(cache_dir, root, options, &cached, found)
+}
+
+/// Cached subdirectories of root which still exist, without revalidating the cache. Dirs removed since are left out
+/// rather than creating sessions in them. None if root was not scanned with these options yet.
+pub fn cached(cache_dir: &Path, root: &Path, options: &ScanOptions) -> Option<Vec<PathBuf>> {
+    let key = (root.as_os_str().as_bytes().to_vec(), options_key(options));
+    let cached = read(&cache_file(cache_dir, root, options), &key)?;
+    return Some(
+        cached
+            .dirs
+            .iter()
+            .map(|dir| PathBuf::from(OsStr::from_bytes(dir)))
+            .filter(|dir| dir.is_dir())
+            .collect(),
+    );
+}
+
+/// Revalidates the cached scan of root using the modification times of root and its ignore files. If root changed it
+/// is scanned again and the cache updated, passing the dirs which are not among the listed ones to found until it
+/// returns false. Callers can show the [`cached`] dirs first and revalidate them without holding up anything else.
+pub fn revalidate<F>(
+    cache_dir: &Path,
+    root: &Path,
+    options: &ScanOptions,
+    listed: &[PathBuf],
+    mut found: F,
+) -> Result<()>
+where
+    F: FnMut(PathBuf) -> Result<bool>,
+{
+    let path = cache_file(cache_dir, root, options);
+    let key = (root.as_os_str().as_bytes().to_vec(), options_key(options));
+    let cached = read(&path, &key);
+
+    let stamp = stamp(root, options);
+    if cached.as_ref().is_some_and(|cached| cached.stamp == stamp) {
+        debug!("Cached scan of '{}' is up to date", root.display());
+        return Ok(());
+    }
+    if cached.is_some() {
+        debug!("Cached scan of '{}' is outdated", root.display());
+    }
+
+    let dirs = crate::scan::subdirs(root, options)?;
+    let _ = write(
+        &path,
+        &CachedScan {
+            root: key.0,
+            options: key.1,
+            stamp,
+            dirs: dirs.iter().map(|dir| dir.as_os_str().as_bytes().to_vec()).collect(),
+        },
+    );
+
+    let listed = listed.iter().collect::<HashSet<_>>();
+    for dir in dirs {
+        if !listed.contains(&dir) && !found(dir)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Removes all cached scans.
+pub fn clear(cache_dir: &Path) -> Result<()> {
+    match fs::remove_dir_all(cache_dir) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).scan_context(format!("Unable to remove cache dir '{}'", cache_dir.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn cache_file(cache_dir: &Path, root: &Path, options: &ScanOptions) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    options_key(options).hash(&mut hasher);
+    return cache_dir.join("dirs").join(format!("{:016x}.json", hasher.finish()));
+}
+
+fn options_key(options: &ScanOptions) -> (bool, bool, bool) {
+    (options.skip_hidden, options.gitignore, options.follow_symlinks)
+}
+
+/// Modification times of everything the scan result of root depends on. Adding, removing or renaming a subdirectory
+/// updates the modification time of root.
+fn stamp(root: &Path, options: &ScanOptions) -> Vec<Option<(u64, u32)>> {
+    let mut paths = vec![root.to_path_buf()];
+    if options.gitignore {
+        paths.extend(IGNORE_FILES.iter().map(|file| root.join(file)));
+    }
+
+    return paths
+        .iter()
+        .map(|path| {
+            let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+            let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+            Some((since_epoch.as_secs(), since_epoch.subsec_nanos()))
+        })
+        .collect();
+}
+
+fn read(path: &Path, key: &(Vec<u8>, (bool, bool, bool))) -> Option<CachedScan> {
+    fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<CachedScan>(&content).ok())
+        .filter(|cached| (&cached.root, &cached.options) == (&key.0, &key.1))
+}
+
+fn write(path: &Path, scan: &CachedScan) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first so concurrent popups never read a partially written cache
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_vec(scan)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    use super::{cache_file, cached, clear, revalidate, subdirs, CachedScan};
+    use crate::config::ScanOptions;
+
+    fn list(cache_dir: &Path, root: &Path) -> Vec<PathBuf> {
+        let mut res = Vec::new();
+        subdirs(cache_dir, root, &ScanOptions::default(), |dir| {
+            res.push(dir);
+            Ok(true)
+        })
+        .unwrap();
+        res
+    }
+
+    #[test]
+    fn cached_dirs_are_revalidated() {
+        let cache = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("a")).unwrap();
+
+        assert_eq!(list(cache.path(), root.path()), vec![root.path().join("a")]);
+        assert!(cache_file(cache.path(), root.path(), &ScanOptions::default()).is_file());
+
+        // Removed dirs of a stale cache are left out, dirs found when revalidating are added
+        fs::create_dir(root.path().join("b")).unwrap();
+        fs::remove_dir(root.path().join("a")).unwrap();
+        assert_eq!(list(cache.path(), root.path()), vec![root.path().join("b")]);
+        assert_eq!(list(cache.path(), root.path()), vec![root.path().join("b")]);
+    }
+
+    #[test]
+    fn unchanged_root_is_not_scanned() {
+        let cache = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("a")).unwrap();
+        list(cache.path(), root.path());
+
+        let path = cache_file(cache.path(), root.path(), &ScanOptions::default());
+        let mut cached: CachedScan = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        cached.dirs = vec![elsewhere.path().as_os_str().as_bytes().to_vec()];
+        fs::write(&path, serde_json::to_vec(&cached).unwrap()).unwrap();
+
+        assert_eq!(list(cache.path(), root.path()), vec![elsewhere.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn revalidating_passes_only_new_dirs() {
+        let cache = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("a")).unwrap();
+        let options = ScanOptions::default();
+        let revalidated = |listed: &[PathBuf]| {
+            let mut res = Vec::new();
+            revalidate(cache.path(), root.path(), &options, listed, |dir| {
+                res.push(dir);
+                Ok(true)
+            })
+            .unwrap();
+            res
+        };
+
+        assert_eq!(cached(cache.path(), root.path(), &options), None);
+        assert_eq!(revalidated(&[]), vec![root.path().join("a")]);
+        fs::create_dir(root.path().join("b")).unwrap();
+        let listed = cached(cache.path(), root.path(), &options).unwrap();
+
+        assert_eq!(listed, vec![root.path().join("a")]);
+        assert_eq!(revalidated(&listed), vec![root.path().join("b")]);
+        assert_eq!(revalidated(&listed), Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn clear_removes_cache() {
+        let cache = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        list(cache.path(), root.path());
+
+        clear(cache.path()).unwrap();
+
+        assert!(!cache.path().exists());
+        clear(cache.path()).unwrap();
+    }
+}
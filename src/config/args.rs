@@ -1,6 +1,7 @@
 use std::{env, path::PathBuf};
 
-use clap::{command, Parser, Subcommand};
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
 
 #[derive(Parser, Debug, Default)]
 #[command(name = "Tmux Sessionizer")]
@@ -12,12 +13,36 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = false, help = "Disable the big banner in list mode")]
     pub no_banner: bool,
 
-    #[arg(short, long, default_value_t = false, help = "Dry run, dont switch session.")]
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Dry run, print the tmux commands instead of running them."
+    )]
     pub dry_run: bool,
 
-    #[arg(short, long, default_value_t = false, help = "Enable verbose output.")]
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Enable verbose output. Same as '--log-level debug'."
+    )]
     pub verbose: bool,
 
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Log level, one of off, error, warn, info, debug or trace. Overrides $TMS_LOG which defaults to warn."
+    )]
+    pub log_level: Option<LevelFilter>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Append log messages to tms.log in $XDG_STATE_HOME/tmux-sessionizer instead of printing them to stderr."
+    )]
+    pub log_file: bool,
+
     #[arg(
         short,
         long,
@@ -50,11 +75,27 @@ pub enum Command {
     List {
         #[arg(long, short, default_value_t = false)]
         grouped: bool,
+        #[arg(
+            long,
+            short,
+            default_value_t = false,
+            conflicts_with = "grouped",
+            help = "Open the selection as a window in the current session instead of switching to its session"
+        )]
+        window: bool,
     },
     /// Directly switches to session
     Switch {
         #[arg(long, short, default_value_t = false)]
         grouped: bool,
+        #[arg(
+            long,
+            short,
+            default_value_t = false,
+            conflicts_with = "grouped",
+            help = "Open a window in the current session instead of switching to the session"
+        )]
+        window: bool,
         #[arg(
             name = "NAME",
             help = "Session name to switch directly to. Will create the session if it does not exist"
@@ -65,6 +106,18 @@ pub enum Command {
     Config {
         #[arg(short, long, default_value_t = false)]
         example: bool,
+        #[command(subcommand)]
+        action: Option<ConfigCommand>,
+    },
+    /// Manages the cache of scanned directories
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Prints the preview of an entry or a running session the way the finder shows it
+    Preview {
+        #[arg(name = "NAME", help = "Name of the item as the finder shows it")]
+        name: String,
     },
     Kill {
         #[arg(short, long, default_value_t = false, group = "kill")]
@@ -74,6 +127,18 @@ pub enum Command {
     },
 }
 
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Validates the config and prints the resolved paths of every entry
+    Check,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum CacheCommand {
+    /// Removes all cached directory scans
+    Clear,
+}
+
 fn get_default_config_path() -> PathBuf {
     let mut path = PathBuf::new();
     if let Ok(dir) = env::var("XDG_CONFIG_HOME") {
@@ -88,3 +153,33 @@ fn get_default_config_path() -> PathBuf {
     path.push("sessionizer.toml");
     return path;
 }
+
+pub(crate) fn get_default_cache_dir() -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    if let Ok(dir) = env::var("XDG_CACHE_HOME") {
+        path.push(dir);
+    } else if let Ok(home) = env::var("HOME") {
+        path.push(home);
+        path.push(".cache");
+    } else {
+        return None;
+    }
+    path.push("tmux-sessionizer");
+    return Some(path);
+}
+
+pub(crate) fn get_default_log_file() -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    if let Ok(dir) = env::var("XDG_STATE_HOME") {
+        path.push(dir);
+    } else if let Ok(home) = env::var("HOME") {
+        path.push(home);
+        path.push(".local");
+        path.push("state");
+    } else {
+        return None;
+    }
+    path.push("tmux-sessionizer");
+    path.push("tms.log");
+    return Some(path);
+}
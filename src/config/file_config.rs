@@ -13,6 +13,11 @@ pub struct FileConfig {
     pub preview_cmd: Option<FilePreviewCommands>,
     #[serde(default = "default_preview_width")]
     pub preview_width: u32,
+    #[serde(default = "default_bool_true")]
+    pub cache: bool,
+    #[serde(default = "default_bool_false")]
+    pub control_mode: bool,
+    pub finder: Option<String>,
     pub default_dir: String,
     #[serde(rename = "entry")]
     pub entries: Vec<FileEntry>,
@@ -24,19 +29,32 @@ pub struct FilePreviewCommands {
     pub not_running: Option<Arc<str>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct FileEntry {
     pub kind: FileEntryKind,
     pub name: String,
-    pub workdir: String,
+    pub workdir: Option<String>,
     pub excludes: Option<Vec<String>>,
     pub preview_cmd: Option<FilePreviewCommands>,
+    pub ssh_config: Option<String>,
+    pub source: Option<String>,
+    pub limit: Option<usize>,
+    pub roots: Option<Vec<String>>,
+    pub skip_hidden: Option<bool>,
+    pub gitignore: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub window: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq)]
 pub enum FileEntryKind {
     Dir,
+    #[default]
     Plain,
+    Ssh,
+    Zoxide,
+    Workspace,
+    GitSubmodules,
 }
 
 impl Default for FileConfig {
@@ -46,29 +64,51 @@ impl Default for FileConfig {
             no_banner: true,
             verbose: false,
             sort: true,
+            cache: true,
+            control_mode: false,
+            finder: None,
             preview_cmd: Some(FilePreviewCommands {
-                running:
-                    Arc::from("tmux capture-pane -pe -t $(tmux list-panes -F '#{pane_id}' -s -t '{{name}}' -f '#{window_active}')".to_owned()),
-                not_running: Some(Arc::from("ls -la".to_owned())),
+                running: Arc::from("pane".to_owned()),
+                not_running: Some(Arc::from("tree".to_owned())),
             }),
             preview_width: 30,
             entries: vec![
                 FileEntry {
                     name: "My session".to_owned(),
-                    workdir: "/".to_owned(),
+                    workdir: Some("/".to_owned()),
                     kind: FileEntryKind::Plain,
                     preview_cmd: Some(FilePreviewCommands {
                         running: Arc::from("ls -la".to_owned()),
                         not_running: Some(Arc::from("ls -la".to_owned())),
                     }),
-                    excludes: None,
+                    ..Default::default()
                 },
                 FileEntry {
                     name: "My Projects Dir - {{name}} {{workdir}}".to_owned(),
-                    workdir: "/home/youruser".to_owned(),
+                    workdir: Some("/home/youruser".to_owned()),
                     kind: FileEntryKind::Dir,
-                    preview_cmd: None,
                     excludes: Some(vec!["somedir".to_owned()]),
+                    ..Default::default()
+                },
+                FileEntry {
+                    name: "{{package}}".to_owned(),
+                    workdir: Some("/home/youruser/monorepo".to_owned()),
+                    kind: FileEntryKind::Workspace,
+                    ..Default::default()
+                },
+                FileEntry {
+                    name: "ssh {{name}}".to_owned(),
+                    kind: FileEntryKind::Ssh,
+                    excludes: Some(vec!["github.com".to_owned()]),
+                    ssh_config: Some("~/.ssh/config".to_owned()),
+                    ..Default::default()
+                },
+                FileEntry {
+                    name: "z {{name}}".to_owned(),
+                    kind: FileEntryKind::Zoxide,
+                    limit: Some(20),
+                    roots: Some(vec!["/home/youruser".to_owned()]),
+                    ..Default::default()
                 },
             ],
         }
@@ -1,19 +1,28 @@
 mod args;
 mod file_config;
 
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use crate::config::{
     args::Args,
-    file_config::{FileConfig, FileEntryKind},
+    file_config::{FileConfig, FileEntry, FileEntryKind},
 };
-use anyhow::Result;
-use anyhow::{anyhow, Context};
 use clap::Parser;
+use log::LevelFilter;
+
+use crate::error::{Context, Error, Result};
+use crate::template::{Template, COMMON_PLACEHOLDERS, PREVIEW_PLACEHOLDERS};
 
+pub use args::CacheCommand;
 pub use args::Command;
+pub use args::ConfigCommand;
 pub use file_config::FilePreviewCommands;
 
+const DEFAULT_SSH_CONFIG: &str = "~/.ssh/config";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PreviewCommands {
     pub running: Option<Arc<str>>,
@@ -30,12 +39,18 @@ impl From<FilePreviewCommands> for PreviewCommands {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct Workdir(std::sync::Arc<str>);
+pub struct Workdir(PathBuf);
 
 #[derive(Debug, PartialEq)]
 pub enum Entry {
     Dir(EntryDir),
     Plain(EntryPlain),
+    Ssh(EntrySsh),
+    Zoxide(EntryZoxide),
+    /// Members of a Cargo, pnpm, npm or Go workspace found in workdir.
+    Workspace(EntryDir),
+    /// Submodules declared in `.gitmodules` of the repository in workdir.
+    GitSubmodules(EntryDir),
 }
 
 #[derive(Debug, PartialEq)]
@@ -44,40 +59,212 @@ pub struct EntryDir {
     pub workdir: Workdir,
     pub excludes: Option<Vec<String>>,
     pub preview_cmd: Option<PreviewCommands>,
+    pub scan: ScanOptions,
+    /// Open items as a window in the current session instead of as a session
+    pub window: bool,
+}
+
+/// How a Dir entry scans its workdir for subdirectories.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    /// Skip directories whose name starts with a dot
+    pub skip_hidden: bool,
+    /// Skip directories ignored by `.gitignore` or `.ignore` in the workdir
+    pub gitignore: bool,
+    /// List symlinks pointing to directories, which are skipped by default
+    pub follow_symlinks: bool,
 }
 #[derive(Debug, PartialEq)]
 pub struct EntryPlain {
     pub name: String,
     pub workdir: Workdir,
     pub preview_cmd: Option<PreviewCommands>,
+    /// Open the item as a window in the current session instead of as a session
+    pub window: bool,
+}
+#[derive(Debug, PartialEq)]
+pub struct EntrySsh {
+    pub name: String,
+    pub workdir: Workdir,
+    pub ssh_config: PathBuf,
+    pub excludes: Option<Vec<String>>,
+    pub preview_cmd: Option<PreviewCommands>,
+    /// Open items as a window in the current session instead of as a session
+    pub window: bool,
+}
+#[derive(Debug, PartialEq)]
+pub struct EntryZoxide {
+    pub name: String,
+    /// Plain text file with one directory per line, optionally prefixed by its score. When not set, directories
+    /// are read from `zoxide query -ls`.
+    pub source: Option<PathBuf>,
+    pub limit: Option<usize>,
+    pub roots: Option<Vec<PathBuf>>,
+    pub excludes: Option<Vec<String>>,
+    pub preview_cmd: Option<PreviewCommands>,
+    /// Open items as a window in the current session instead of as a session
+    pub window: bool,
+}
+
+impl Entry {
+    /// Name template of the entry.
+    pub fn name(&self) -> &str {
+        match self {
+            Entry::Dir(e) | Entry::Workspace(e) | Entry::GitSubmodules(e) => &e.name,
+            Entry::Plain(e) => &e.name,
+            Entry::Ssh(e) => &e.name,
+            Entry::Zoxide(e) => &e.name,
+        }
+    }
+
+    /// Checks that the name template and preview commands only use placeholders available for the entry kind.
+    fn validate_templates(&self) -> Result<()> {
+        let (name, preview_cmd, placeholders): (_, _, &[&str]) = match self {
+            Entry::Dir(e) | Entry::GitSubmodules(e) => (&e.name, &e.preview_cmd, &["name", "relpath"]),
+            Entry::Workspace(e) => (&e.name, &e.preview_cmd, &["name", "relpath", "package"]),
+            Entry::Ssh(e) => (&e.name, &e.preview_cmd, &["name"]),
+            Entry::Zoxide(e) => (&e.name, &e.preview_cmd, &["name"]),
+            Entry::Plain(e) => (&e.name, &e.preview_cmd, &[]),
+        };
+
+        Template::parse(name)?.validate(&[COMMON_PLACEHOLDERS, placeholders].concat())?;
+        if let Some(preview_cmd) = preview_cmd {
+            preview_cmd.validate_templates()?;
+        }
+        Ok(())
+    }
+}
+
+impl PreviewCommands {
+    fn validate_templates(&self) -> Result<()> {
+        for cmd in [&self.running, &self.not_running].into_iter().flatten() {
+            Template::parse(cmd)?.validate(&[COMMON_PLACEHOLDERS, PREVIEW_PLACEHOLDERS].concat())?;
+        }
+        Ok(())
+    }
+}
+
+/// Expands value and resolves it against base, the directory of the config file, if it is relative. Relative paths
+/// are canonicalized if they exist.
+fn resolve_path(value: &str, base: &Path) -> Result<PathBuf> {
+    let path = PathBuf::from(crate::utils::envsubst(value)?.as_ref());
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    let path = base.join(path);
+    return Ok(path.canonicalize().unwrap_or(path));
+}
+
+fn expand_path(value: &str, field: &str, base: &Path) -> Result<PathBuf> {
+    resolve_path(value, base).config_context(format!("Invalid '{field}'."))
+}
+
+impl Workdir {
+    fn resolve(value: &str, field: &str, base: &Path) -> Result<Self> {
+        Ok(Self(expand_path(value, field, base)?))
+    }
 }
 
 impl TryFrom<String> for Workdir {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        Ok(Self(crate::utils::envsubst(&value)?))
+        Ok(Self(PathBuf::from(crate::utils::envsubst(&value)?.as_ref())))
+    }
+}
+
+impl From<PathBuf> for Workdir {
+    fn from(value: PathBuf) -> Self {
+        Self(value)
     }
 }
 
-impl AsRef<str> for Workdir {
-    fn as_ref(&self) -> &str {
+impl AsRef<Path> for Workdir {
+    fn as_ref(&self) -> &Path {
         &self.0
     }
 }
 
+/// Fuzzy finder items are picked with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finder {
+    /// skim built into tms, only available with the `skim` feature
+    Skim,
+    /// fzf or sk command, optionally with arguments, running as its own process and showing previews with `tms preview`
+    External(String),
+    /// dmenu-style shell command reading item names from stdin, one per line, and printing the selected one
+    Lines(String),
+}
+
+impl Finder {
+    /// Commands whose first word is fzf or sk, ie. `fzf --exact` or `/usr/bin/sk`, are external finders.
+    fn parse(value: &str) -> Result<Self> {
+        let program = value.split_whitespace().next().map(Path::new).and_then(Path::file_name);
+        match value.trim() {
+            "" => Err(Error::config("'finder' must not be empty.")),
+            "skim" if cfg!(feature = "skim") => Ok(Self::Skim),
+            "skim" => Err(Error::config(crate::prompt::SKIM_MISSING)),
+            command if program.is_some_and(|program| program == "fzf" || program == "sk") => {
+                Ok(Self::External(command.to_owned()))
+            }
+            command => Ok(Self::Lines(command.to_owned())),
+        }
+    }
+}
+
+/// skim if it is built in, fzf otherwise.
+impl Default for Finder {
+    fn default() -> Self {
+        if cfg!(feature = "skim") {
+            Self::Skim
+        } else {
+            Self::External("fzf".to_owned())
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub config_path: PathBuf,
     pub command: Option<Command>,
     pub hide_banner: bool,
-    pub verbose: bool,
     pub sort: bool,
     pub preview_commands: Option<PreviewCommands>,
     pub preview_width: u32,
     pub default_dir: Workdir,
     pub eval_mode: bool,
     pub dry_run: bool,
+    /// Directory scanned directories are cached in, `None` if caching is disabled
+    pub cache_dir: Option<PathBuf>,
+    /// Whether to query tmux over a control mode connection instead of running it for every query
+    pub control_mode: bool,
+    /// Level of log messages, `None` to take it from `$TMS_LOG`
+    pub log_level: Option<LevelFilter>,
+    /// File log messages are appended to, stderr if `None`
+    pub log_file: Option<PathBuf>,
+    pub finder: Finder,
+}
+
+/// Config of an empty config file with `default_dir = "/"`.
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            config_path: PathBuf::new(),
+            command: None,
+            hide_banner: false,
+            sort: true,
+            preview_commands: None,
+            preview_width: 30,
+            default_dir: PathBuf::from("/").into(),
+            eval_mode: false,
+            dry_run: false,
+            cache_dir: None,
+            control_mode: false,
+            log_level: None,
+            log_file: None,
+            finder: Finder::default(),
+        }
+    }
 }
 
 pub struct ConfigWithEntries(Config, Vec<Entry>);
@@ -147,8 +334,9 @@ impl Config {
         let args = Args::parse();
         let file_config: FileConfig = toml::from_str(
             &std::fs::read_to_string(&args.config)
-                .context(format!("Unable to read config file '{:?}'", &args.config))?,
-        )?;
+                .config_context(format!("Unable to read config file '{:?}'", &args.config))?,
+        )
+        .config_context(format!("Unable to parse config file '{}'", args.config.display()))?;
         Self::construct(args, file_config)
     }
 
@@ -158,30 +346,22 @@ impl Config {
         let preview_commands =
             MaybePreviewCommands::from((preview.as_ref(), preview_no_session.as_ref(), file_config.preview_cmd)).0;
 
+        if let Some(ref preview_commands) = preview_commands {
+            preview_commands
+                .validate_templates()
+                .config_context("Preview commands are invalid.")?;
+        }
+
+        let config_dir = args.config.parent().map(Path::to_path_buf).unwrap_or_default();
+        let default_dir = Workdir::resolve(&file_config.default_dir, "default_dir", &config_dir)?;
+
         let mut entries = Vec::with_capacity(file_config.entries.len());
         for ele in file_config.entries {
-            let res = match ele.kind {
-                FileEntryKind::Dir => Entry::Dir(EntryDir {
-                    name: ele.name,
-                    workdir: ele.workdir.try_into()?,
-                    excludes: ele.excludes,
-                    preview_cmd: MaybePreviewCommands::from((preview_commands.as_ref(), ele.preview_cmd)).0,
-                }),
-                FileEntryKind::Plain => {
-                    if ele.excludes.is_some() {
-                        return Err(anyhow!(
-                            "Entry '{}' is invalid. Excludes are not allowed on 'Plain' entries.",
-                            ele.name
-                        ));
-                    }
-                    Entry::Plain(EntryPlain {
-                        name: ele.name,
-                        workdir: ele.workdir.try_into()?,
-                        preview_cmd: MaybePreviewCommands::from((preview_commands.as_ref(), ele.preview_cmd)).0,
-                    })
-                }
-            };
-            entries.push(res);
+            let name = ele.name.clone();
+            entries.push(
+                Self::construct_entry(ele, preview_commands.as_ref(), &default_dir, &config_dir)
+                    .config_context(format!("Entry '{name}' is invalid."))?,
+            );
         }
 
         return Ok(ConfigWithEntries(
@@ -191,18 +371,177 @@ impl Config {
                 config_path: args.config,
                 command: args.command.take(),
                 hide_banner: args.no_banner || file_config.no_banner,
-                verbose: args.verbose || file_config.verbose,
+                log_level: args
+                    .log_level
+                    .or((args.verbose || file_config.verbose).then_some(LevelFilter::Debug)),
+                log_file: args.log_file.then(args::get_default_log_file).flatten(),
+                finder: file_config
+                    .finder
+                    .as_deref()
+                    .map(Finder::parse)
+                    .transpose()?
+                    .unwrap_or_default(),
                 sort: args.sort || file_config.sort,
                 preview_width: file_config.preview_width,
                 dry_run: args.dry_run,
-                default_dir: file_config.default_dir.try_into()?,
+                default_dir,
+                cache_dir: file_config.cache.then(args::get_default_cache_dir).flatten(),
+                control_mode: file_config.control_mode,
             },
             entries,
         ));
     }
 
+    fn construct_entry(
+        ele: FileEntry,
+        preview_commands: Option<&PreviewCommands>,
+        default_dir: &Workdir,
+        config_dir: &Path,
+    ) -> Result<Entry> {
+        for (field, allowed_kind, present) in [
+            ("ssh_config", FileEntryKind::Ssh, ele.ssh_config.is_some()),
+            ("source", FileEntryKind::Zoxide, ele.source.is_some()),
+            ("limit", FileEntryKind::Zoxide, ele.limit.is_some()),
+            ("roots", FileEntryKind::Zoxide, ele.roots.is_some()),
+            ("skip_hidden", FileEntryKind::Dir, ele.skip_hidden.is_some()),
+            ("gitignore", FileEntryKind::Dir, ele.gitignore.is_some()),
+            ("follow_symlinks", FileEntryKind::Dir, ele.follow_symlinks.is_some()),
+        ] {
+            if present && ele.kind != allowed_kind {
+                return Err(Error::config(format!(
+                    "'{field}' is only allowed on '{allowed_kind:?}' entries."
+                )));
+            }
+        }
+
+        for exclude in ele.excludes.iter().flatten() {
+            glob::Pattern::new(exclude).config_context(format!("Invalid exclude pattern '{exclude}'."))?;
+        }
+
+        let preview_cmd = MaybePreviewCommands::from((preview_commands, ele.preview_cmd)).0;
+        let window = ele.window.unwrap_or(false);
+        let workdir = ele
+            .workdir
+            .map(|workdir| Workdir::resolve(&workdir, "workdir", config_dir))
+            .transpose()?;
+
+        let res = match ele.kind {
+            FileEntryKind::Dir | FileEntryKind::Workspace | FileEntryKind::GitSubmodules => {
+                let entry = EntryDir {
+                    workdir: workdir
+                        .ok_or_else(|| Error::config(format!("Workdir is required on '{:?}' entries.", ele.kind)))?,
+                    name: ele.name,
+                    excludes: ele.excludes,
+                    preview_cmd,
+                    scan: ScanOptions {
+                        skip_hidden: ele.skip_hidden.unwrap_or(false),
+                        gitignore: ele.gitignore.unwrap_or(false),
+                        follow_symlinks: ele.follow_symlinks.unwrap_or(false),
+                    },
+                    window,
+                };
+                match ele.kind {
+                    FileEntryKind::Workspace => Entry::Workspace(entry),
+                    FileEntryKind::GitSubmodules => Entry::GitSubmodules(entry),
+                    _ => Entry::Dir(entry),
+                }
+            }
+            FileEntryKind::Plain => {
+                if ele.excludes.is_some() {
+                    return Err(Error::config("Excludes are not allowed on 'Plain' entries."));
+                }
+                Entry::Plain(EntryPlain {
+                    workdir: workdir.ok_or_else(|| Error::config("Workdir is required on 'Plain' entries."))?,
+                    name: ele.name,
+                    preview_cmd,
+                    window,
+                })
+            }
+            FileEntryKind::Ssh => Entry::Ssh(EntrySsh {
+                name: ele.name,
+                workdir: workdir.unwrap_or_else(|| default_dir.clone()),
+                ssh_config: expand_path(
+                    ele.ssh_config.as_deref().unwrap_or(DEFAULT_SSH_CONFIG),
+                    "ssh_config",
+                    config_dir,
+                )?,
+                excludes: ele.excludes,
+                preview_cmd,
+                window,
+            }),
+            FileEntryKind::Zoxide => {
+                if workdir.is_some() {
+                    return Err(Error::config("Workdir is not allowed on 'Zoxide' entries."));
+                }
+                Entry::Zoxide(EntryZoxide {
+                    name: ele.name,
+                    source: ele
+                        .source
+                        .map(|source| expand_path(&source, "source", config_dir))
+                        .transpose()?,
+                    limit: ele.limit,
+                    roots: ele
+                        .roots
+                        .map(|roots| {
+                            roots
+                                .iter()
+                                .map(|root| expand_path(root, "roots", config_dir))
+                                .collect()
+                        })
+                        .transpose()?,
+                    excludes: ele.excludes,
+                    preview_cmd,
+                    window,
+                })
+            }
+        };
+        res.validate_templates()?;
+
+        return Ok(res);
+    }
+
+    /// Describes the directories every entry lists items from, with relative paths resolved.
+    pub fn check(&self, entries: &[Entry]) -> String {
+        let describe = |path: &Path| {
+            let missing = if path.exists() { "" } else { " (does not exist)" };
+            format!("{}{missing}", path.display())
+        };
+
+        let mut res = format!(
+            "Config '{}' is valid.\n\ndefault_dir: {}\n",
+            self.config_path.display(),
+            describe(self.default_dir.as_ref())
+        );
+        for entry in entries {
+            let (kind, name, paths) = match entry {
+                Entry::Dir(e) => ("Dir", &e.name, vec![describe(e.workdir.as_ref())]),
+                Entry::Workspace(e) => ("Workspace", &e.name, vec![describe(e.workdir.as_ref())]),
+                Entry::GitSubmodules(e) => ("GitSubmodules", &e.name, vec![describe(e.workdir.as_ref())]),
+                Entry::Plain(e) => ("Plain", &e.name, vec![describe(e.workdir.as_ref())]),
+                Entry::Ssh(e) => (
+                    "Ssh",
+                    &e.name,
+                    vec![
+                        describe(e.workdir.as_ref()),
+                        format!("hosts from {}", describe(&e.ssh_config)),
+                    ],
+                ),
+                Entry::Zoxide(e) => {
+                    let mut paths = match e.source {
+                        Some(ref source) => vec![format!("dirs from {}", describe(source))],
+                        None => vec!["dirs from zoxide".to_owned()],
+                    };
+                    paths.extend(e.roots.iter().flatten().map(|root| format!("under {}", describe(root))));
+                    ("Zoxide", &e.name, paths)
+                }
+            };
+            res.push_str(&format!("{kind} '{name}': {}\n", paths.join(", ")));
+        }
+        return res;
+    }
+
     pub fn example_config() -> Result<String> {
-        toml::to_string(&FileConfig::default()).context("Unable to serialize example config")
+        toml::to_string(&FileConfig::default()).config_context("Unable to serialize example config")
     }
 }
 
@@ -210,12 +549,13 @@ impl Config {
 #[allow(clippy::unwrap_used)]
 mod tests {
     mod construct {
+        use log::LevelFilter;
         use std::{path::PathBuf, str::FromStr, sync::Arc};
         use test_case::test_case;
 
         use crate::config::{
             file_config::{FileEntry, FileEntryKind},
-            Command, Entry, EntryDir, EntryPlain, PreviewCommands,
+            Command, Entry, EntryDir, EntryPlain, EntrySsh, EntryZoxide, Finder, PreviewCommands, ScanOptions,
         };
 
         use super::super::{args::Args, file_config::FileConfig, Config};
@@ -232,16 +572,30 @@ mod tests {
                 FileEntry {
                     kind: FileEntryKind::Plain,
                     name: "plain name".to_owned(),
-                    workdir: "/home/test/".to_owned(),
-                    excludes: None,
-                    preview_cmd: None,
+                    workdir: Some("/home/test/".to_owned()),
+                    ..Default::default()
                 },
                 FileEntry {
                     kind: FileEntryKind::Dir,
                     name: "plain name".to_owned(),
-                    workdir: "/home/test/".to_owned(),
+                    workdir: Some("/home/test/".to_owned()),
                     excludes: Some(vec!["dir1".to_owned()]),
-                    preview_cmd: None,
+                    window: Some(true),
+                    ..Default::default()
+                },
+                FileEntry {
+                    kind: FileEntryKind::Ssh,
+                    name: "ssh {{name}}".to_owned(),
+                    ssh_config: Some("/home/test/.ssh/config".to_owned()),
+                    ..Default::default()
+                },
+                FileEntry {
+                    kind: FileEntryKind::Zoxide,
+                    name: "z {{name}}".to_owned(),
+                    source: Some("/home/test/dirs.txt".to_owned()),
+                    limit: Some(10),
+                    roots: Some(vec!["/home/test/code".to_owned()]),
+                    ..Default::default()
                 },
             ];
 
@@ -252,7 +606,8 @@ mod tests {
                 Entry::Plain(EntryPlain {
                     name: "plain name".to_owned(),
                     workdir: "/home/test/".to_owned().try_into().unwrap(),
-                    preview_cmd: None
+                    preview_cmd: None,
+                    window: false,
                 })
             );
             assert_eq!(
@@ -262,10 +617,128 @@ mod tests {
                     workdir: "/home/test/".to_owned().try_into().unwrap(),
                     preview_cmd: None,
                     excludes: Some(vec!["dir1".to_owned()]),
+                    scan: ScanOptions::default(),
+                    window: true,
+                })
+            );
+            assert_eq!(
+                result.1[2],
+                Entry::Ssh(EntrySsh {
+                    name: "ssh {{name}}".to_owned(),
+                    workdir: "/".to_owned().try_into().unwrap(),
+                    ssh_config: PathBuf::from("/home/test/.ssh/config"),
+                    preview_cmd: None,
+                    excludes: None,
+                    window: false,
+                })
+            );
+            assert_eq!(
+                result.1[3],
+                Entry::Zoxide(EntryZoxide {
+                    name: "z {{name}}".to_owned(),
+                    source: Some(PathBuf::from("/home/test/dirs.txt")),
+                    limit: Some(10),
+                    roots: Some(vec![PathBuf::from("/home/test/code")]),
+                    preview_cmd: None,
+                    excludes: None,
+                    window: false,
                 })
             );
         }
 
+        #[test_case(FileEntryKind::Dir, "{{name}} {{package}}", false ; "dir without package")]
+        #[test_case(FileEntryKind::Workspace, "{{package}} in {{workdir}}", true ; "workspace with package")]
+        #[test_case(FileEntryKind::Dir, "{{name}} {{nope}}", false ; "unknown placeholder")]
+        #[test_case(FileEntryKind::Dir, "{{ relpath | replace:\"/\":\"-\" }}@{{hostname}}", true ; "dir relpath")]
+        #[test_case(FileEntryKind::Plain, "{{git_branch}} {{env.USER}}", true ; "plain common")]
+        #[test_case(FileEntryKind::Plain, "{{name}}", false ; "plain with name")]
+        #[test_case(FileEntryKind::Dir, "{{name", false ; "unclosed placeholder")]
+        fn name_templates(kind: FileEntryKind, name: &str, valid: bool) {
+            let (args, mut file) = setup();
+            file.entries = vec![FileEntry {
+                kind,
+                name: name.to_owned(),
+                workdir: Some("/home/test/".to_owned()),
+                ..Default::default()
+            }];
+
+            assert_eq!(Config::construct(args, file).is_ok(), valid);
+        }
+
+        #[test_case(None, false ; "skipped by default")]
+        #[test_case(Some(true), true ; "followed when enabled")]
+        fn dir_symlinks(follow_symlinks: Option<bool>, expected: bool) {
+            let (args, mut file) = setup();
+            file.entries = vec![FileEntry {
+                kind: FileEntryKind::Dir,
+                name: "{{name}}".to_owned(),
+                workdir: Some("/home/test/".to_owned()),
+                follow_symlinks,
+                ..Default::default()
+            }];
+
+            let result = Config::construct(args, file).unwrap();
+
+            assert!(matches!(&result.1[0], Entry::Dir(e) if e.scan.follow_symlinks == expected));
+        }
+
+        #[test]
+        fn preview_command_templates() {
+            let (mut args, file) = setup();
+            args.preview = Some("ls {{nope}}".to_owned());
+
+            assert!(Config::construct(args, file).is_err());
+        }
+
+        #[test]
+        fn expansion_errors_name_the_entry() {
+            let (args, mut file) = setup();
+            file.entries = vec![FileEntry {
+                kind: FileEntryKind::Dir,
+                name: "broken".to_owned(),
+                workdir: Some("${TMS_SURELY_UNSET_VAR}/code".to_owned()),
+                ..Default::default()
+            }];
+
+            let err = Config::construct(args, file).err().unwrap();
+            let err = std::iter::successors(Some(&err as &dyn std::error::Error), |err| err.source())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": ");
+
+            assert!(err.contains("Entry 'broken' is invalid"), "{err}");
+            assert!(err.contains("'TMS_SURELY_UNSET_VAR' is not set"), "{err}");
+        }
+
+        #[test]
+        fn kind_specific_fields() {
+            let (args, mut file) = setup();
+            file.entries = vec![FileEntry {
+                kind: FileEntryKind::Dir,
+                name: "name".to_owned(),
+                workdir: Some("/home/test/".to_owned()),
+                limit: Some(10),
+                ..Default::default()
+            }];
+
+            assert!(Config::construct(args, file).is_err());
+        }
+
+        #[test_case(FileEntryKind::Dir ; "dir")]
+        #[test_case(FileEntryKind::Plain ; "plain")]
+        #[test_case(FileEntryKind::Workspace ; "workspace")]
+        #[test_case(FileEntryKind::GitSubmodules ; "git submodules")]
+        fn workdir_required(kind: FileEntryKind) {
+            let (args, mut file) = setup();
+            file.entries = vec![FileEntry {
+                kind,
+                name: "name".to_owned(),
+                ..Default::default()
+            }];
+
+            assert!(Config::construct(args, file).is_err());
+        }
+
         #[test]
         fn default_dir() {
             let (args, mut file) = setup();
@@ -276,6 +749,36 @@ mod tests {
             assert_eq!(result.0.default_dir, "some def dir".to_owned().try_into().unwrap());
         }
 
+        #[test]
+        fn relative_paths_are_resolved_against_config_dir() {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::create_dir(dir.path().join("projects")).unwrap();
+            let (mut args, mut file) = setup();
+            args.config = dir.path().join("sessionizer.toml");
+            file.default_dir = "projects/../projects".to_owned();
+            file.entries = vec![FileEntry {
+                kind: FileEntryKind::Dir,
+                name: "{{name}}".to_owned(),
+                workdir: Some("missing".to_owned()),
+                ..Default::default()
+            }];
+
+            let (config, entries) = Config::construct(args, file).unwrap().value();
+            let projects = dir.path().join("projects").canonicalize().unwrap();
+
+            assert_eq!(config.default_dir, projects.clone().into());
+            assert_eq!(
+                config.check(&entries).lines().skip(2).collect::<Vec<_>>(),
+                vec![
+                    format!("default_dir: {}", projects.display()),
+                    format!(
+                        "Dir '{{{{name}}}}': {} (does not exist)",
+                        dir.path().join("missing").display()
+                    ),
+                ]
+            );
+        }
+
         #[test]
         fn preview_width() {
             let (args, mut file) = setup();
@@ -313,6 +816,7 @@ mod tests {
             args.command = Some(Command::Switch {
                 name: "test".to_owned(),
                 grouped: true,
+                window: false,
             });
 
             let result = Config::construct(args, file).unwrap();
@@ -322,6 +826,7 @@ mod tests {
                 Some(Command::Switch {
                     name: "test".to_owned(),
                     grouped: true,
+                    window: false,
                 })
             );
         }
@@ -339,17 +844,69 @@ mod tests {
             assert_eq!(result.0.hide_banner, expected);
         }
 
-        #[test_case(true, false, true ; "enabled in args, disabled in file")]
-        #[test_case(false, true, true ; "disabled in args, enabled in file")]
-        #[test_case(false, false, false ; "disabled in args, disabled in file")]
-        #[test_case(true, true, true ; "enabled in args, enabled in file")]
-        fn verbose(arg_val: bool, file_val: bool, expected: bool) {
+        #[test_case(true, false, Some(LevelFilter::Debug) ; "enabled in args, disabled in file")]
+        #[test_case(false, true, Some(LevelFilter::Debug) ; "disabled in args, enabled in file")]
+        #[test_case(false, false, None ; "disabled in args, disabled in file")]
+        #[test_case(true, true, Some(LevelFilter::Debug) ; "enabled in args, enabled in file")]
+        fn verbose(arg_val: bool, file_val: bool, expected: Option<LevelFilter>) {
             let (mut args, mut file) = setup();
             args.verbose = arg_val;
             file.verbose = file_val;
 
             let result = Config::construct(args, file).unwrap();
-            assert_eq!(result.0.verbose, expected);
+            assert_eq!(result.0.log_level, expected);
+        }
+
+        #[test_case(None, false, None ; "taken from the environment")]
+        #[test_case(None, true, Some(LevelFilter::Debug) ; "verbose logs debug messages")]
+        #[test_case(Some(LevelFilter::Trace), true, Some(LevelFilter::Trace) ; "level wins over verbose")]
+        #[test_case(Some(LevelFilter::Off), false, Some(LevelFilter::Off) ; "level in args")]
+        fn log_level(arg_val: Option<LevelFilter>, verbose: bool, expected: Option<LevelFilter>) {
+            let (mut args, file) = setup();
+            args.log_level = arg_val;
+            args.verbose = verbose;
+
+            let result = Config::construct(args, file).unwrap();
+            assert_eq!(result.0.log_level, expected);
+        }
+
+        #[test_case(None, Finder::default() ; "default")]
+        #[test_case(Some("sk"), Finder::External("sk".to_owned()) ; "external sk")]
+        #[test_case(Some(" fzf "), Finder::External("fzf".to_owned()) ; "external fzf")]
+        #[test_case(Some("fzf --exact"), Finder::External("fzf --exact".to_owned()) ; "external with args")]
+        #[test_case(Some("/usr/bin/sk"), Finder::External("/usr/bin/sk".to_owned()) ; "external path")]
+        #[test_case(Some("fzf-tmux -p"), Finder::Lines("fzf-tmux -p".to_owned()) ; "other program")]
+        #[test_case(Some("rofi -dmenu"), Finder::Lines("rofi -dmenu".to_owned()) ; "line based command")]
+        fn finder(file_val: Option<&str>, expected: Finder) {
+            let (args, mut file) = setup();
+            file.finder = file_val.map(str::to_owned);
+
+            let result = Config::construct(args, file).unwrap();
+            assert_eq!(result.0.finder, expected);
+        }
+
+        #[test]
+        fn default_finder_is_built_in_skim_or_fzf() {
+            let (args, mut file) = setup();
+            file.finder = Some("skim".to_owned());
+
+            let skim = Config::construct(args, file);
+
+            if cfg!(feature = "skim") {
+                assert_eq!(skim.unwrap().0.finder, Finder::Skim);
+                assert_eq!(Finder::default(), Finder::Skim);
+            } else {
+                assert!(skim.is_err());
+                assert_eq!(Finder::default(), Finder::External("fzf".to_owned()));
+            }
+        }
+
+        #[test]
+        fn empty_finder_is_invalid() {
+            let (args, mut file) = setup();
+            file.finder = Some(" ".to_owned());
+
+            assert!(Config::construct(args, file).is_err());
         }
 
         #[test_case(true, false, true ; "enabled in args, disabled in file")]
@@ -0,0 +1,200 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::io;
+
+use crate::tmux::{Feature, Version};
+
+/// Error the underlying error of a variant is boxed as.
+pub type Source = Box<dyn StdError + Send + Sync>;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Everything that can go wrong in tmux sessionizer. Each variant maps to its own process exit code, see
+/// [`Error::exit_code`].
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The config file could not be read, parsed or contains invalid values.
+    #[error("{message}")]
+    Config {
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// Reading a directory or a file referenced by an entry failed.
+    #[error("{message}")]
+    Scan {
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// An external program, ie. tmux or zoxide, is not installed.
+    #[error("Unable to run '{program}'. Is it installed?")]
+    NotInstalled { program: String },
+    /// An external program could not be started for another reason than it not being installed.
+    #[error("Unable to run '{program}'")]
+    Spawn {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+    /// An external program exited unsuccessfully.
+    #[error("'{program} {}' failed{}{}", args.join(" "), code.map(|code| format!(" with exit code {code}")).unwrap_or_default(), stderr_suffix(stderr))]
+    Command {
+        program: String,
+        args: Vec<String>,
+        code: Option<i32>,
+        stderr: String,
+    },
+    /// The installed tmux is too old for a command or flag tmux sessionizer uses.
+    #[error("'{feature}' needs tmux {required} or newer, found tmux {found}")]
+    Unsupported {
+        feature: Feature,
+        required: Version,
+        found: Version,
+    },
+    /// Output of an external program or the content of a file is not in the expected format.
+    #[error("{message}")]
+    Parse {
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// No entry or running session has an item with the requested name.
+    #[error("No entry or running session has an item called '{0}'")]
+    UnknownItem(String),
+    /// The current session was requested but no client is attached to any session.
+    #[error("No session is attached")]
+    NoAttachedSession,
+    /// The fuzzy finder failed.
+    #[error("{0}")]
+    Picker(String),
+    /// The user closed the fuzzy finder without selecting anything.
+    #[error("No item was selected")]
+    Aborted,
+}
+
+impl Error {
+    pub fn config(message: impl Display) -> Self {
+        Self::Config {
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn scan(message: impl Display) -> Self {
+        Self::Scan {
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn parse(message: impl Display) -> Self {
+        Self::Parse {
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    /// Error for program failing to start, telling apart it not being installed.
+    pub fn spawn(program: &str, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::NotFound => Self::NotInstalled {
+                program: program.to_owned(),
+            },
+            _ => Self::Spawn {
+                program: program.to_owned(),
+                source,
+            },
+        }
+    }
+
+    /// Exit code the binary exits with on this error. Follows sysexits.h where it has a fitting code and the shell
+    /// conventions for programs which cannot be run and for interrupts otherwise.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Config { .. } => 78,
+            Self::Scan { .. } => 74,
+            Self::NotInstalled { .. } => 127,
+            Self::Spawn { .. } => 126,
+            Self::Command { .. } => 69,
+            Self::Unsupported { .. } => 71,
+            Self::Parse { .. } => 65,
+            Self::UnknownItem(_) => 64,
+            Self::NoAttachedSession => 75,
+            Self::Picker(_) => 70,
+            Self::Aborted => 130,
+        }
+    }
+}
+
+fn stderr_suffix(stderr: &str) -> String {
+    match stderr.trim() {
+        "" => String::new(),
+        stderr => format!(": {stderr}"),
+    }
+}
+
+/// Wraps errors into the variant matching what the library was doing when they happened, keeping them as the source.
+pub(crate) trait Context<T> {
+    fn config_context(self, message: impl Display) -> Result<T>;
+    fn scan_context(self, message: impl Display) -> Result<T>;
+    fn parse_context(self, message: impl Display) -> Result<T>;
+}
+
+impl<T, E: Into<Source>> Context<T> for std::result::Result<T, E> {
+    fn config_context(self, message: impl Display) -> Result<T> {
+        self.map_err(|err| Error::Config {
+            message: message.to_string(),
+            source: Some(err.into()),
+        })
+    }
+
+    fn scan_context(self, message: impl Display) -> Result<T> {
+        self.map_err(|err| Error::Scan {
+            message: message.to_string(),
+            source: Some(err.into()),
+        })
+    }
+
+    fn parse_context(self, message: impl Display) -> Result<T> {
+        self.map_err(|err| Error::Parse {
+            message: message.to_string(),
+            source: Some(err.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::{Context, Error};
+
+    #[test]
+    fn command_error_names_the_invocation() {
+        let err = Error::Command {
+            program: "tmux".to_owned(),
+            args: vec!["kill-session".to_owned(), "-t".to_owned(), "proj".to_owned()],
+            code: Some(1),
+            stderr: "can't find session: proj\n".to_owned(),
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "'tmux kill-session -t proj' failed with exit code 1: can't find session: proj"
+        );
+        assert_eq!(err.exit_code(), 69);
+    }
+
+    #[test]
+    fn context_keeps_the_source() {
+        let err = Err::<(), _>(Error::parse("Invalid placeholder"))
+            .config_context("Entry 'a' is invalid.")
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Config { .. }));
+        assert_eq!(err.to_string(), "Entry 'a' is invalid.");
+        assert_eq!(err.source().unwrap().to_string(), "Invalid placeholder");
+    }
+}
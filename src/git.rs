@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Context, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submodule {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Reads submodules declared in the `.gitmodules` file of the repository at root. Returned paths are joined with
+/// root.
+pub fn read_submodules(root: &Path) -> Result<Vec<Submodule>> {
+    let path = root.join(".gitmodules");
+    let content = fs::read_to_string(&path).scan_context(format!("Unable to read '{}'", path.display()))?;
+
+    return Ok(parse_submodules(&content)
+        .into_iter()
+        .map(|submodule| Submodule {
+            path: root.join(submodule.path),
+            ..submodule
+        })
+        .collect());
+}
+
+fn parse_submodules(content: &str) -> Vec<Submodule> {
+    parse_config(content)
+        .into_iter()
+        .filter_map(|section| {
+            let name = subsection(&section.header, "submodule")?;
+            let path = section.get("path")?;
+            Some(Submodule {
+                name: name.to_owned(),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+/// Returns the current branch of the repository containing dir, or the abbreviated commit hash when HEAD is
+/// detached.
+pub fn branch(dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir(dir)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    return match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_owned())
+        }
+        None => Some(head.chars().take(7).collect()),
+    };
+}
+
+/// Returns `owner/repo` of the `origin` remote of the repository containing dir, or of the first remote if there is
+/// no `origin`.
+pub fn remote_repo(dir: &Path) -> Option<String> {
+    let git_dir = git_dir(dir)?;
+    // Linked worktrees keep their config in the common dir
+    let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir,
+    };
+    let config = parse_config(&fs::read_to_string(common_dir.join("config")).ok()?);
+    let remotes = config
+        .iter()
+        .filter_map(|section| Some((subsection(&section.header, "remote")?, section.get("url")?)))
+        .collect::<Vec<_>>();
+    let (_, url) = remotes
+        .iter()
+        .find(|(name, _)| *name == "origin")
+        .or_else(|| remotes.first())?;
+
+    return Some(repo_from_url(url));
+}
+
+fn repo_from_url(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let mut segments = url.rsplit(['/', ':']).filter(|s| !s.is_empty());
+    let repo = segments.next().unwrap_or_default();
+    return match segments.next() {
+        Some(owner) => format!("{owner}/{repo}"),
+        None => repo.to_owned(),
+    };
+}
+
+/// Finds the git dir of the repository containing dir. Follows `gitdir:` files used by worktrees and submodules.
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let content = fs::read_to_string(&dot_git).ok()?;
+            return Some(ancestor.join(content.trim().strip_prefix("gitdir:")?.trim()));
+        }
+    }
+    return None;
+}
+
+struct Section {
+    header: String,
+    entries: Vec<(String, String)>,
+}
+
+impl Section {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parses the subset of git config syntax used by `.gitmodules` and `.git/config`.
+fn parse_config(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(Section {
+                header: header.trim().to_owned(),
+                entries: Vec::new(),
+            });
+        } else if let (Some(section), Some((key, value))) = (sections.last_mut(), line.split_once('=')) {
+            section
+                .entries
+                .push((key.trim().to_owned(), value.trim().trim_matches('"').to_owned()));
+        }
+    }
+
+    return sections;
+}
+
+/// Returns the subsection name of a header like `submodule "name"` if the section is kind.
+fn subsection<'a>(header: &'a str, kind: &str) -> Option<&'a str> {
+    let (section, name) = header.split_once(char::is_whitespace)?;
+    if !section.eq_ignore_ascii_case(kind) {
+        return None;
+    }
+    return Some(name.trim().trim_matches('"'));
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use test_case::test_case;
+
+    use super::{branch, parse_submodules, read_submodules, remote_repo, repo_from_url, Submodule};
+
+    #[test]
+    fn parses_gitmodules() {
+        let content = r#"
+# vendored libraries
+[submodule "vendor/lib"]
+	path = vendor/lib
+	url = https://example.com/lib.git
+[core]
+	path = not/a/submodule
+[submodule "docs"]
+	url = https://example.com/docs.git
+	Path = "third party/docs"
+	branch = main
+[submodule "no-path"]
+	url = https://example.com/broken.git
+"#;
+
+        assert_eq!(
+            parse_submodules(content),
+            vec![
+                Submodule {
+                    name: "vendor/lib".to_owned(),
+                    path: PathBuf::from("vendor/lib"),
+                },
+                Submodule {
+                    name: "docs".to_owned(),
+                    path: PathBuf::from("third party/docs"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_are_joined_with_root() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".gitmodules"), "[submodule \"a\"]\n\tpath = libs/a\n").unwrap();
+
+        assert_eq!(
+            read_submodules(dir.path()).unwrap(),
+            vec![Submodule {
+                name: "a".to_owned(),
+                path: dir.path().join("libs/a"),
+            }]
+        );
+    }
+
+    #[test]
+    fn branch_and_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/feature/x\n").unwrap();
+        fs::write(
+            dir.path().join(".git/config"),
+            "[core]\n\tbare = false\n[remote \"upstream\"]\n\turl = https://example.com/up/stream.git\n[remote \"origin\"]\n\turl = git@github.com:me/project.git\n",
+        )
+        .unwrap();
+
+        assert_eq!(branch(&dir.path().join("src/nested")), Some("feature/x".to_owned()));
+        assert_eq!(remote_repo(dir.path()), Some("me/project".to_owned()));
+    }
+
+    #[test]
+    fn detached_head_in_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let git_dir = dir.path().join("main/.git/worktrees/wt");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::create_dir_all(dir.path().join("wt")).unwrap();
+        fs::write(dir.path().join("wt/.git"), format!("gitdir: {}\n", git_dir.display())).unwrap();
+        fs::write(git_dir.join("HEAD"), "0123456789abcdef\n").unwrap();
+        fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        fs::write(
+            dir.path().join("main/.git/config"),
+            "[remote \"fork\"]\n\turl = https://example.com/fork/repo\n",
+        )
+        .unwrap();
+
+        assert_eq!(branch(&dir.path().join("wt")), Some("0123456".to_owned()));
+        assert_eq!(remote_repo(&dir.path().join("wt")), Some("fork/repo".to_owned()));
+    }
+
+    #[test_case("git@github.com:owner/repo.git", "owner/repo" ; "scp like")]
+    #[test_case("https://gitlab.com/group/sub/repo/", "sub/repo" ; "https nested")]
+    #[test_case("ssh://git@host:22/owner/repo.git", "owner/repo" ; "ssh url")]
+    #[test_case("/srv/repo.git", "srv/repo" ; "local path")]
+    fn repo_names(url: &str, expected: &str) {
+        assert_eq!(repo_from_url(url), expected);
+    }
+
+    #[test]
+    fn missing_gitmodules_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(read_submodules(dir.path()).is_err());
+    }
+}
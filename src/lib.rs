@@ -1,47 +1,74 @@
 #![allow(clippy::needless_return)]
 #![deny(clippy::expect_used, clippy::unwrap_used, clippy::panic)]
+pub mod cache;
 pub mod config;
+pub mod error;
+pub mod git;
+pub mod logging;
+pub mod plan;
+pub mod preview;
 pub mod prompt;
 pub mod prompt_item;
+pub mod scan;
+pub mod ssh_config;
+pub mod template;
 pub mod tmux;
 pub mod utils;
+pub mod workspace;
+pub mod zoxide;
 
-extern crate skim;
+pub use error::{Error, Result};
 
-use anyhow::Context;
-use anyhow::Result;
+use std::io::Write;
+use std::sync::Arc;
+
+use log::debug;
+
+use config::CacheCommand;
 use config::Command;
 use config::Config;
+use config::ConfigCommand;
+use config::Entry;
+use plan::Plan;
+use plan::State;
+use prompt::Picker;
+use prompt_item::Preview;
 use prompt_item::PromptItem;
 use tmux::Execute;
 use tmux::Tmux;
 
-pub fn run<E: Execute>(prompt_items: Vec<PromptItem>, tmux: &Tmux<E>, config: &Config) -> Result<()> {
+pub fn run<E: Execute + Send + Sync + 'static, P: Picker>(
+    entries: Vec<Entry>,
+    tmux: &Arc<Tmux<E>>,
+    config: &Config,
+    picker: &P,
+) -> Result<()> {
+    let show = |entries: Vec<Entry>| -> Result<PromptItem> {
+        let sessions = tmux.get_active_sessions()?;
+        picker
+            .pick(
+                |send| prompt_item::stream_prompt_items(entries, config, sessions, tmux.executor(), send),
+                tmux,
+                config,
+            )?
+            .ok_or(Error::Aborted)
+    };
+
     match config.command {
-        Some(Command::List { grouped }) => {
-            if let Some(selected_item) = prompt::show(prompt_items, config)? {
-                if config.dry_run {
-                    return Ok(());
-                }
-                switch_to_selected_item(&selected_item, tmux, config, grouped)?;
-            }
-            Ok(())
+        Some(Command::List { grouped, window }) => {
+            switch_to_selected_item(&show(entries)?, tmux, config, grouped, window)
         }
-        None => {
-            if let Some(selected_item) = prompt::show(prompt_items, config)? {
-                if config.dry_run {
-                    return Ok(());
-                }
-                switch_to_selected_item(&selected_item, tmux, config, false)?;
-            }
+        None => switch_to_selected_item(&show(entries)?, tmux, config, false, false),
+        Some(Command::Config {
+            action: Some(ConfigCommand::Check),
+            ..
+        }) => {
+            print!("{}", config.check(&entries));
             Ok(())
         }
-        Some(Command::Config { example }) => {
+        Some(Command::Config { example, .. }) => {
             if example {
-                println!(
-                    "{}",
-                    Config::example_config().context("Unable to serialize dummy config")?
-                );
+                println!("{}", Config::example_config()?);
                 return Ok(());
             }
 
@@ -55,83 +82,186 @@ pub fn run<E: Execute>(prompt_items: Vec<PromptItem>, tmux: &Tmux<E>, config: &C
             println!("{content}");
             Ok(())
         }
-        Some(Command::Switch { ref name, grouped }) => {
+        Some(Command::Switch {
+            ref name,
+            grouped,
+            window,
+        }) => {
             let item = &PromptItem::new(name.to_owned(), config.default_dir.to_owned());
-            switch_to_selected_item(item, tmux, config, grouped)
-        }
-        Some(Command::Kill { current, .. }) if current => {
-            if config.dry_run {
-                return Ok(());
-            }
-            let current_session = prompt_items
-                .iter()
-                .find(|i| i.stats.as_ref().map_or(false, |s| s.attached))
-                .context("Cannot kill current session because no session is attached.")?;
-            tmux.kill_session(&current_session.name)?;
-            Ok(())
+            switch_to_selected_item(item, tmux, config, grouped, window)
         }
-        Some(Command::Kill {
-            name: Some(ref name), ..
+        Some(Command::Cache {
+            action: CacheCommand::Clear,
         }) => {
-            if config.dry_run {
-                return Ok(());
+            if let Some(ref cache_dir) = config.cache_dir {
+                cache::clear(cache_dir)?;
             }
-
-            tmux.kill_session(name)?;
             Ok(())
         }
-        Some(Command::Kill { .. }) => {
-            if let Some(selected_item) = prompt::show(prompt_items, config)? {
-                tmux.kill_session(&selected_item.name)?;
-            }
-            Ok(())
+        Some(Command::Preview { ref name }) => preview(entries, tmux, config, name, &mut std::io::stdout().lock()),
+        Some(Command::Kill { current, .. }) if current => {
+            let sessions = tmux.get_active_sessions()?;
+            let (current_session, _) = sessions
+                .value_ref()
+                .iter()
+                .find(|(_, stats)| stats.attached)
+                .ok_or(Error::NoAttachedSession)?;
+            perform(&plan::kill(current_session), tmux, config)
         }
+        Some(Command::Kill {
+            name: Some(ref name), ..
+        }) => perform(&plan::kill(name), tmux, config),
+        Some(Command::Kill { .. }) => perform(&plan::kill(&show(entries)?.name), tmux, config),
     }
 }
 
+/// Switches to the session of item, or opens it as a window in the current session if window is set or the item asks
+/// for it.
 fn switch_to_selected_item<E: Execute>(
     item: &PromptItem,
     tmux: &Tmux<E>,
     config: &Config,
     grouped: bool,
+    window: bool,
 ) -> Result<()> {
-    let tmux_running = tmux.is_tmux_running()?;
-    let inside_tmux = std::env::var("TMUX").is_ok();
+    let window = window || item.window;
+    let mut state = State::query(tmux, &item.name)?;
+    if window && state.inside_tmux {
+        state.windows = tmux.current_windows()?;
+    }
+
+    debug!("Selected item: {item:?}");
+    debug!("State: {state:?}");
 
-    if config.verbose {
-        println!("Selected_item: {item:?}");
-        println!("inside_tmux: {inside_tmux:?}");
-        println!("is_tmux_running()?: {tmux_running:?}");
+    let plan = if window {
+        plan::window(item, &state, config.eval_mode)
+    } else {
+        plan::switch(item, &state, config.eval_mode, grouped)
     };
+    perform(&plan, tmux, config)?;
+    if config.eval_mode && !config.dry_run {
+        println!("{}", item.name);
+    }
+    return Ok(());
+}
+
+/// Writes the preview of the item called name to out, running the preview command if it has one. Output of the command
+/// on stderr goes to our stderr.
+fn preview<E: Execute + Sync, W: Write>(
+    entries: Vec<Entry>,
+    tmux: &Tmux<E>,
+    config: &Config,
+    name: &str,
+    out: &mut W,
+) -> Result<()> {
+    let sessions = tmux.get_active_sessions()?;
+    let item = prompt_item::find_prompt_item(entries, config, sessions, tmux.executor(), name)?
+        .ok_or_else(|| Error::UnknownItem(name.to_owned()))?;
+    debug!("Previewing {item:?}");
 
-    if !tmux_running && !inside_tmux {
-        if config.eval_mode {
-            tmux.new_session(&item.name, item.workdir.as_ref(), true)?.print();
-            println!("{}", item.name);
-        } else {
-            tmux.new_session(&item.name, item.workdir.as_ref(), false)?.print();
+    let written = match item.preview_content(tmux) {
+        Preview::Text(text) | Preview::AnsiText(text) => out.write_all(text.as_bytes()),
+        Preview::Command(cmd) => {
+            let output = tmux.executor().execute("sh", &["-c", &cmd])?;
+            std::io::stderr().write_all(&output.stderr).ok();
+            out.write_all(&output.stdout)
         }
+    };
+    // The finder closing the preview early is no error
+    written.and_then(|_| out.flush()).ok();
+    return Ok(());
+}
+
+/// Applies the plan, or only prints it in dry run mode.
+fn perform<E: Execute>(plan: &Plan, tmux: &Tmux<E>, config: &Config) -> Result<()> {
+    if config.dry_run {
+        print!("{plan}");
         return Ok(());
     }
+    tmux.apply(plan)
+}
 
-    if !tmux.has_session(&item.name)? {
-        tmux.new_session(&item.name, item.workdir.as_ref(), true)?.print();
-    }
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::PathBuf;
 
-    if config.eval_mode {
-        println!("{}", item.name);
-        return Ok(());
+    use test_case::test_case;
+
+    use super::{preview, switch_to_selected_item};
+    use crate::config::{Config, Entry, EntryPlain, PreviewCommands};
+    use crate::error::Error;
+    use crate::prompt_item::PromptItem;
+    use crate::tmux::{RecordingExecutor, Tmux};
+
+    #[test_case(false, &["tmux new-session -ds proj -c /code/proj ; switch-client -t proj"] ; "applies plan")]
+    #[test_case(true, &[] ; "dry run only queries")]
+    fn switch(dry_run: bool, expected: &[&str]) {
+        let executor = RecordingExecutor::new().respond(&["tmux", "list-sessions"], 0, "session 1 other\n");
+        let config = Config {
+            dry_run,
+            ..Default::default()
+        };
+        let tmux = Tmux::new_with_executor(executor).with_inside_tmux(true);
+        let item = PromptItem::new("proj".to_owned(), PathBuf::from("/code/proj").into());
+
+        switch_to_selected_item(&item, &tmux, &config, false, false).unwrap();
+
+        let commands = tmux.executor().commands();
+        assert!(commands[0].starts_with("tmux list-sessions"));
+        assert_eq!(commands[1..], *expected);
     }
 
-    if grouped {
-        tmux.new_grouped_session(&item.name)?.print();
+    #[test]
+    fn item_opening_as_window_selects_existing_window() {
+        let executor = RecordingExecutor::new()
+            .respond(&["tmux", "list-sessions"], 0, "session 1 other\n")
+            .respond(&["tmux", "list-windows"], 0, "@1 /\n@2 /code/proj\n");
+        let tmux = Tmux::new_with_executor(executor).with_inside_tmux(true);
+        let mut item = PromptItem::new("proj".to_owned(), PathBuf::from("/code/proj").into());
+        item.window = true;
+
+        switch_to_selected_item(&item, &tmux, &Config::default(), false, false).unwrap();
+
+        let commands = tmux.executor().commands();
+        assert!(commands[1].starts_with("tmux list-windows -F"));
+        assert_eq!(commands[2..], ["tmux select-window -t @2"]);
     }
 
-    if !inside_tmux {
-        tmux.attach(&item.name)?.print();
-    } else {
-        tmux.switch_client(&item.name)?.print();
+    #[test]
+    fn preview_runs_preview_command_of_entry() {
+        let executor = RecordingExecutor::new().respond(&["sh"], 0, "README.md\n");
+        let tmux = Tmux::new_with_executor(executor);
+        let entries = vec![Entry::Plain(EntryPlain {
+            name: "proj".to_owned(),
+            workdir: PathBuf::from("/code/proj").into(),
+            preview_cmd: Some(PreviewCommands {
+                running: None,
+                not_running: Some("ls {{workdir}}".into()),
+            }),
+            window: false,
+        })];
+        let mut out = Vec::new();
+
+        preview(entries, &tmux, &Config::default(), "proj", &mut out).unwrap();
+
+        assert_eq!(out, b"README.md\n");
+        assert_eq!(tmux.executor().calls().last().unwrap(), &["sh", "-c", "ls /code/proj"]);
     }
 
-    return Ok(());
+    #[test]
+    fn preview_of_unknown_name_is_an_error() {
+        let executor = RecordingExecutor::new().respond(&["tmux", "list-sessions"], 0, "session 1 other\n");
+        let tmux = Tmux::new_with_executor(executor);
+        let entries = vec![Entry::Plain(EntryPlain {
+            name: "proj".to_owned(),
+            workdir: PathBuf::from("/code/proj").into(),
+            preview_cmd: None,
+            window: false,
+        })];
+
+        let res = preview(entries, &tmux, &Config::default(), "unknown", &mut Vec::new());
+
+        assert!(matches!(res, Err(Error::UnknownItem(name)) if name == "unknown"));
+    }
 }
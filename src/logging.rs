@@ -0,0 +1,101 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use env_logger::{Builder, Target};
+use log::LevelFilter;
+
+use crate::config::Config;
+use crate::error::{Context, Result};
+
+/// Environment variable with the log level or env_logger filter directives, ie. `debug` or
+/// `tmux_sessionizer::tmux=trace`.
+pub const LOG_ENV: &str = "TMS_LOG";
+
+/// Log files are rotated once they grow beyond this size, keeping one rotated file.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Modules of the library and the binary, messages of dependencies like skim are not logged.
+const MODULES: &[&str] = &["tmux_sessionizer", "tms"];
+
+/// Sets up logging to stderr or the log file of config. Only warnings and errors are logged unless a level is set in
+/// `$TMS_LOG` or config, the latter taking precedence.
+pub fn init(config: &Config) -> Result<()> {
+    let mut builder = Builder::new();
+    for module in MODULES {
+        builder.filter_module(module, LevelFilter::Warn);
+    }
+    if let Ok(filters) = std::env::var(LOG_ENV) {
+        builder.parse_filters(&qualify(&filters));
+    }
+    if let Some(level) = config.log_level {
+        for module in MODULES {
+            builder.filter_module(module, level);
+        }
+    }
+
+    builder.format_timestamp_millis();
+    if let Some(ref path) = config.log_file {
+        let file = open(path).config_context(format!("Unable to open log file '{}'", path.display()))?;
+        builder.target(Target::Pipe(Box::new(file)));
+    }
+    builder.try_init().config_context("Unable to initialize logging")?;
+    Ok(())
+}
+
+/// Turns a bare level like `debug` into directives for our own modules, so it does not enable logging of every
+/// dependency.
+fn qualify(filters: &str) -> String {
+    let filters = filters.trim();
+    match filters.parse::<LevelFilter>() {
+        Ok(_) => MODULES
+            .iter()
+            .map(|module| format!("{module}={filters}"))
+            .collect::<Vec<_>>()
+            .join(","),
+        Err(_) => filters.to_owned(),
+    }
+}
+
+/// Opens the log file for appending, moving it to `<path>.1` first if it grew too large.
+fn open(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() > MAX_LOG_SIZE) {
+        let mut rotated = PathBuf::from(path).into_os_string();
+        rotated.push(".1");
+        fs::rename(path, rotated)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+
+    use super::{open, qualify, MAX_LOG_SIZE};
+
+    #[test]
+    fn bare_level_only_applies_to_own_modules() {
+        assert_eq!(qualify("debug"), "tmux_sessionizer=debug,tms=debug");
+        assert_eq!(qualify("skim=info"), "skim=info");
+    }
+
+    #[test]
+    fn log_file_is_rotated_when_too_large() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("tms.log");
+
+        drop(open(&path).unwrap());
+        fs::write(&path, vec![b'x'; MAX_LOG_SIZE as usize + 1]).unwrap();
+        drop(open(&path).unwrap());
+
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        assert_eq!(
+            fs::metadata(dir.path().join("state").join("tms.log.1")).unwrap().len(),
+            MAX_LOG_SIZE + 1
+        );
+    }
+}
@@ -1,23 +1,48 @@
 #![allow(clippy::needless_return)]
-extern crate skim;
 
 use tmux_sessionizer::config::Config;
-use tmux_sessionizer::prompt_item::IntoPromptItems;
+use tmux_sessionizer::logging;
 use tmux_sessionizer::run;
-use tmux_sessionizer::tmux::Tmux;
+use tmux_sessionizer::tmux::{ControlExecutor, Tmux};
 
-use anyhow::Result;
+use std::process::ExitCode;
+use std::sync::Arc;
 
-fn main() -> Result<()> {
+use log::debug;
+
+use tmux_sessionizer::Error;
+
+fn main() -> ExitCode {
+    match try_main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let code = err.downcast_ref::<Error>().map_or(1, Error::exit_code);
+            // Closing the picker is not worth a message
+            if !matches!(err.downcast_ref::<Error>(), Some(Error::Aborted)) {
+                eprintln!("Error: {err:?}");
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn try_main() -> anyhow::Result<()> {
     let (config, entries) = Config::read()?.value();
 
-    if config.verbose {
-        println!("{config:#?}");
-    };
+    logging::init(&config)?;
+    debug!("{config:#?}");
+
+    if config.control_mode {
+        // Fall back to running tmux for every query if there is no server to connect to yet or tmux is too old
+        if let Ok(executor) = ControlExecutor::connect(None) {
+            let tmux = Arc::new(Tmux::new_with_executor(executor));
+            run(entries, &tmux, &config, &config.finder)?;
+            return Ok(());
+        }
+    }
 
-    let tmux = Tmux::new(&config);
-    let active_sessions = tmux.get_active_sessions()?;
-    let prompt_items = entries.into_prompt_items(&config, active_sessions)?;
+    let tmux = Arc::new(Tmux::new());
 
-    run(prompt_items, &tmux, &config)
+    run(entries, &tmux, &config, &config.finder)?;
+    Ok(())
 }
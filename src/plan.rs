@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::prompt_item::PromptItem;
+use crate::template::shell_quote;
+use crate::tmux::{escape, Execute, Tmux, Window};
+
+/// Single tmux invocation of a [`Plan`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// Creates a session running command in workdir, attaching to it unless detached
+    NewSession {
+        name: String,
+        workdir: PathBuf,
+        command: Option<String>,
+        detached: bool,
+    },
+    /// Creates a detached session sharing the windows of the session group
+    NewGroupedSession {
+        name: String,
+        group: String,
+    },
+    Attach {
+        name: String,
+    },
+    SwitchClient {
+        name: String,
+    },
+    KillSession {
+        name: String,
+    },
+    /// Creates a window running command in workdir in the current session, selecting it unless detached
+    NewWindow {
+        name: String,
+        workdir: PathBuf,
+        command: Option<String>,
+        detached: bool,
+    },
+    SelectWindow {
+        id: String,
+    },
+}
+
+impl Step {
+    /// Arguments tmux is invoked with to perform the step.
+    pub fn args(&self) -> Vec<&OsStr> {
+        match self {
+            Self::NewSession {
+                name,
+                workdir,
+                command,
+                detached,
+            } => {
+                let mut args = vec![
+                    OsStr::new("new-session"),
+                    OsStr::new(if *detached { "-ds" } else { "-s" }),
+                    OsStr::new(name),
+                    OsStr::new("-c"),
+                    workdir.as_os_str(),
+                ];
+                if let Some(command) = command {
+                    args.push(OsStr::new(command));
+                }
+                args
+            }
+            Self::NewGroupedSession { name, group } => {
+                ["new-session", "-d", "-t", group, "-s", name].map(OsStr::new).to_vec()
+            }
+            Self::Attach { name } => ["attach", "-t", name].map(OsStr::new).to_vec(),
+            Self::SwitchClient { name } => ["switch-client", "-t", name].map(OsStr::new).to_vec(),
+            Self::KillSession { name } => ["kill-session", "-t", name].map(OsStr::new).to_vec(),
+            Self::NewWindow {
+                name,
+                workdir,
+                command,
+                detached,
+            } => {
+                let mut args = vec![OsStr::new("new-window")];
+                if *detached {
+                    args.push(OsStr::new("-d"));
+                }
+                args.extend([
+                    OsStr::new("-c"),
+                    workdir.as_os_str(),
+                    OsStr::new("-n"),
+                    OsStr::new(name),
+                ]);
+                if let Some(command) = command {
+                    args.push(OsStr::new(command));
+                }
+                args
+            }
+            Self::SelectWindow { id } => ["select-window", "-t", id].map(OsStr::new).to_vec(),
+        }
+    }
+}
+
+/// Operations to perform on tmux, built up front so they can be printed instead of executed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan(Vec<Step>);
+
+impl Plan {
+    pub fn steps(&self) -> &[Step] {
+        &self.0
+    }
+}
+
+impl From<Vec<Step>> for Plan {
+    fn from(value: Vec<Step>) -> Self {
+        Self(value)
+    }
+}
+
+/// Prints the plan as shell-ready `tmux ...` lines.
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.0 {
+            let args = step
+                .args()
+                .iter()
+                .map(|arg| shell_quote(&escape(arg).to_string_lossy()).into_owned())
+                .collect::<Vec<_>>();
+            writeln!(f, "tmux {}", args.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// What tmux looks like at the moment, everything switching to a session depends on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct State {
+    pub tmux_running: bool,
+    pub inside_tmux: bool,
+    /// Whether the session to switch to already exists
+    pub session_exists: bool,
+    /// Names of all running sessions
+    pub sessions: HashSet<String>,
+    /// Windows of the current session, only queried when opening a window
+    pub windows: Vec<Window>,
+}
+
+impl State {
+    /// Queries tmux for the state relevant to switching to session_name with a single invocation.
+    pub fn query<E: Execute>(tmux: &Tmux<E>, session_name: &str) -> Result<Self> {
+        let sessions = tmux.sessions()?;
+        return Ok(Self {
+            tmux_running: sessions.is_some(),
+            inside_tmux: tmux.is_inside_tmux(),
+            session_exists: sessions
+                .as_ref()
+                .is_some_and(|sessions| sessions.value_ref().contains_key(session_name)),
+            sessions: sessions
+                .map(|sessions| sessions.value().into_keys().collect())
+                .unwrap_or_default(),
+            windows: Vec::new(),
+        });
+    }
+}
+
+/// Plans switching to item. The session is created if it does not exist yet. With eval_mode it is only created,
+/// detached, and with grouped a new session grouped with it is switched to instead.
+pub fn switch(item: &PromptItem, state: &State, eval_mode: bool, grouped: bool) -> Plan {
+    let new_session = |detached| Step::NewSession {
+        name: item.name.to_owned(),
+        workdir: item.workdir.as_ref().to_path_buf(),
+        command: item.command.to_owned(),
+        detached,
+    };
+
+    if !state.tmux_running && !state.inside_tmux {
+        return Plan(vec![new_session(eval_mode)]);
+    }
+
+    let mut steps = Vec::new();
+    if !state.session_exists {
+        steps.push(new_session(true));
+    }
+    if eval_mode {
+        return Plan(steps);
+    }
+
+    let target = if grouped {
+        let name = grouped_name(&item.name, &state.sessions);
+        steps.push(Step::NewGroupedSession {
+            name: name.clone(),
+            group: item.name.to_owned(),
+        });
+        name
+    } else {
+        item.name.to_owned()
+    };
+
+    steps.push(if state.inside_tmux {
+        Step::SwitchClient { name: target }
+    } else {
+        Step::Attach { name: target }
+    });
+    return Plan(steps);
+}
+
+/// Plans opening item as a window in the current session, or selecting the window whose active pane is in its workdir
+/// already. Items running a command, like ssh, always get a new window. With eval_mode the window is only created, in
+/// the background. Outside of tmux there is no current session, so item is switched to as a session instead.
+pub fn window(item: &PromptItem, state: &State, eval_mode: bool) -> Plan {
+    if !state.inside_tmux {
+        return switch(item, state, eval_mode, false);
+    }
+
+    let existing = state
+        .windows
+        .iter()
+        .find(|window| item.command.is_none() && window.workdir == item.workdir.as_ref());
+    return match existing {
+        Some(_) if eval_mode => Plan::default(),
+        Some(window) => Plan(vec![Step::SelectWindow {
+            id: window.id.to_owned(),
+        }]),
+        None => Plan(vec![Step::NewWindow {
+            name: item.name.to_owned(),
+            workdir: item.workdir.as_ref().to_path_buf(),
+            command: item.command.to_owned(),
+            detached: eval_mode,
+        }]),
+    };
+}
+
+/// Plans killing the session.
+pub fn kill(session_name: &str) -> Plan {
+    Plan(vec![Step::KillSession {
+        name: session_name.to_owned(),
+    }])
+}
+
+/// First of `name-1`, `name-2`, ... which is not taken by a running session, the way tmux names grouped sessions.
+fn grouped_name(name: &str, sessions: &HashSet<String>) -> String {
+    return (1..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| !sessions.contains(candidate))
+        .unwrap_or_else(|| name.to_owned());
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::PathBuf;
+
+    use test_case::test_case;
+
+    use super::{switch, window, State};
+    use crate::prompt_item::PromptItem;
+    use crate::tmux::Window;
+
+    fn plan(state: State, eval_mode: bool, grouped: bool) -> Vec<String> {
+        let item = PromptItem::new("proj".to_owned(), PathBuf::from("/code/proj").into());
+        switch(&item, &state, eval_mode, grouped)
+            .to_string()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    fn state(inside_tmux: bool, session_exists: bool) -> State {
+        State {
+            tmux_running: true,
+            inside_tmux,
+            session_exists,
+            sessions: if session_exists {
+                ["proj".to_owned()].into()
+            } else {
+                Default::default()
+            },
+            windows: Vec::new(),
+        }
+    }
+
+    #[test_case(false, &["tmux new-session -s proj -c /code/proj"] ; "attaches new server")]
+    #[test_case(true, &["tmux new-session -ds proj -c /code/proj"] ; "eval mode starts detached")]
+    fn server_not_running(eval_mode: bool, expected: &[&str]) {
+        assert_eq!(plan(State::default(), eval_mode, false), expected);
+    }
+
+    #[test_case(false, false, &["tmux new-session -ds proj -c /code/proj", "tmux attach -t proj"] ; "outside new")]
+    #[test_case(false, true, &["tmux attach -t proj"] ; "outside existing")]
+    #[test_case(true, false, &["tmux new-session -ds proj -c /code/proj", "tmux switch-client -t proj"] ; "inside new")]
+    #[test_case(true, true, &["tmux switch-client -t proj"] ; "inside existing")]
+    fn server_running(inside_tmux: bool, existing: bool, expected: &[&str]) {
+        assert_eq!(plan(state(inside_tmux, existing), false, false), expected);
+    }
+
+    #[test]
+    fn inside_tmux_without_server_creates_detached_session() {
+        let state = State {
+            inside_tmux: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            plan(state, false, false),
+            vec!["tmux new-session -ds proj -c /code/proj", "tmux switch-client -t proj"]
+        );
+    }
+
+    #[test_case(false, &["tmux new-session -ds proj -c /code/proj"] ; "new session")]
+    #[test_case(true, &[] ; "existing session")]
+    fn eval_mode_does_not_switch(existing: bool, expected: &[&str]) {
+        assert_eq!(plan(state(true, existing), true, false), expected);
+    }
+
+    #[test_case(false, "tmux attach -t proj-2" ; "outside")]
+    #[test_case(true, "tmux switch-client -t proj-2" ; "inside")]
+    fn grouped_switches_to_grouped_session(inside_tmux: bool, expected: &str) {
+        let mut state = state(inside_tmux, true);
+        state.sessions.insert("proj-1".to_owned());
+
+        assert_eq!(
+            plan(state, false, true),
+            vec!["tmux new-session -d -t proj -s proj-2", expected]
+        );
+    }
+
+    #[test]
+    fn plan_is_shell_ready() {
+        let mut item = PromptItem::new("my proj".to_owned(), PathBuf::from("/code/it's").into());
+        item.command = Some("ssh host".to_owned());
+
+        assert_eq!(
+            switch(&item, &State::default(), false, false).to_string(),
+            "tmux new-session -s 'my proj' -c '/code/it'\\''s' 'ssh host'\n"
+        );
+    }
+
+    fn window_plan(windows: &[(&str, &str)], command: Option<&str>, eval_mode: bool) -> String {
+        let mut state = state(true, true);
+        state.windows = windows
+            .iter()
+            .map(|(id, workdir)| Window {
+                id: id.to_string(),
+                workdir: PathBuf::from(workdir),
+            })
+            .collect();
+        let mut item = PromptItem::new("proj".to_owned(), PathBuf::from("/code/proj").into());
+        item.command = command.map(str::to_owned);
+
+        window(&item, &state, eval_mode).to_string()
+    }
+
+    #[test_case(&[("@1", "/code/other")], None, false, "tmux new-window -c /code/proj -n proj\n" ; "new window")]
+    #[test_case(&[("@1", "/code/other"), ("@4", "/code/proj/")], None, false, "tmux select-window -t @4\n" ; "existing window")]
+    #[test_case(&[("@4", "/code/proj")], Some("ssh host"), false, "tmux new-window -c /code/proj -n proj 'ssh host'\n" ; "command always opens new window")]
+    #[test_case(&[], None, true, "tmux new-window -d -c /code/proj -n proj\n" ; "eval mode creates detached window")]
+    #[test_case(&[("@4", "/code/proj")], None, true, "" ; "eval mode with existing window")]
+    fn opens_window_in_current_session(
+        windows: &[(&str, &str)],
+        command: Option<&str>,
+        eval_mode: bool,
+        expected: &str,
+    ) {
+        assert_eq!(window_plan(windows, command, eval_mode), expected);
+    }
+
+    #[test]
+    fn window_outside_tmux_switches_to_session() {
+        let item = PromptItem::new("proj".to_owned(), PathBuf::from("/code/proj").into());
+
+        assert_eq!(
+            window(&item, &state(false, true), false).to_string(),
+            "tmux attach -t proj\n"
+        );
+    }
+}
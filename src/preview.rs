@@ -0,0 +1,240 @@
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Context, Error, Result};
+use crate::tmux::{Execute, Executor, Output, Tmux};
+
+const TREE_MAX_DEPTH: usize = 3;
+const TREE_MAX_ENTRIES: usize = 300;
+const README_MAX_LINES: usize = 200;
+const GIT_LOG_COUNT: &str = "10";
+
+/// Previews implemented natively, selectable by name in place of a preview command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuiltinPreview {
+    /// Content of the active pane of the session
+    Pane,
+    /// Windows of the session with the command running in their active pane
+    Windows,
+    /// Directory tree of the workdir
+    Tree,
+    /// First README found in the workdir
+    Readme,
+    /// Branch, dirty files and recent commits of the workdir
+    Git,
+}
+
+impl FromStr for BuiltinPreview {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "pane" => Ok(Self::Pane),
+            "windows" => Ok(Self::Windows),
+            "tree" => Ok(Self::Tree),
+            "readme" => Ok(Self::Readme),
+            "git" => Ok(Self::Git),
+            _ => Err(Error::parse(format!("Unknown builtin preview '{s}'"))),
+        }
+    }
+}
+
+impl BuiltinPreview {
+    /// Renders the preview for a session and its workdir. The result can contain ANSI escape sequences.
+    pub fn render<E: Execute>(&self, tmux: &Tmux<E>, session_name: &str, workdir: &Path) -> Result<String> {
+        match self {
+            Self::Pane => Ok(stdout(tmux.capture_pane(session_name)?)),
+            Self::Windows => Ok(stdout(tmux.list_windows(
+                session_name,
+                "#{?window_active,*, } #{window_index}: #{window_name} (#{pane_current_command}) #{window_panes} pane(s)",
+            )?)),
+            Self::Tree => tree(workdir),
+            Self::Readme => readme(workdir),
+            Self::Git => git(&Executor, workdir),
+        }
+    }
+}
+
+fn stdout(output: Output) -> String {
+    return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+fn tree(root: &Path) -> Result<String> {
+    let mut res = format!("\x1b[1;34m{}\x1b[0m\n", root.display());
+    let mut remaining = TREE_MAX_ENTRIES;
+    tree_level(root, "", 1, &mut remaining, &mut res)?;
+    if remaining == 0 {
+        res.push_str("...\n");
+    }
+    return Ok(res);
+}
+
+fn tree_level(dir: &Path, prefix: &str, depth: usize, remaining: &mut usize, res: &mut String) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .scan_context(format!("Unable to read dir '{}'.", dir.display()))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() != ".git")
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let count = entries.len();
+    for (i, entry) in entries.into_iter().enumerate() {
+        if *remaining == 0 {
+            return Ok(());
+        }
+        *remaining -= 1;
+
+        let last = i + 1 == count;
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let connector = if last { "└── " } else { "├── " };
+        if is_dir {
+            let _ = writeln!(res, "{prefix}{connector}\x1b[1;34m{name}\x1b[0m");
+        } else {
+            let _ = writeln!(res, "{prefix}{connector}{name}");
+        }
+
+        if is_dir && depth < TREE_MAX_DEPTH {
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            tree_level(&entry.path(), &prefix, depth + 1, remaining, res)?;
+        }
+    }
+    Ok(())
+}
+
+fn readme(dir: &Path) -> Result<String> {
+    let mut candidates = fs::read_dir(dir)
+        .scan_context(format!("Unable to read dir '{}'.", dir.display()))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with("readme"))
+        })
+        .collect::<Vec<_>>();
+    // Prefer the shortest name, ie. README over README.md over README.de.md
+    candidates.sort_by_key(|path| (path.as_os_str().len(), path.clone()));
+
+    let Some(path) = candidates.first() else {
+        return Ok(format!("No README found in '{}'", dir.display()));
+    };
+    let content = fs::read(path).scan_context(format!("Unable to read '{}'", path.display()))?;
+    return Ok(String::from_utf8_lossy(&content).lines().take(README_MAX_LINES).fold(
+        String::new(),
+        |mut acc, line| {
+            acc.push_str(line);
+            acc.push('\n');
+            acc
+        },
+    ));
+}
+
+fn git<E: Execute>(executor: &E, dir: &Path) -> Result<String> {
+    let git = |args: &[&str]| -> Result<String> {
+        let mut all_args = vec![OsStr::new("-C"), dir.as_os_str()];
+        all_args.extend(args.iter().map(OsStr::new));
+        let output = executor.execute("git", &all_args)?.check("git", &all_args)?;
+        Ok(stdout(output))
+    };
+
+    let Ok(branch) = git(&["rev-parse", "--abbrev-ref", "HEAD"]) else {
+        return Ok(format!("'{}' is not a git repository", dir.display()));
+    };
+    let status = git(&["-c", "color.status=always", "status", "--short"])?;
+    let log = git(&["log", "--oneline", "--decorate", "--color=always", "-n", GIT_LOG_COUNT]).unwrap_or_default();
+
+    let mut res = format!("\x1b[1mBranch:\x1b[0m {}\n\n", branch.trim());
+    if status.is_empty() {
+        res.push_str("Working tree clean\n");
+    } else {
+        res.push_str("\x1b[1mChanges:\x1b[0m\n");
+        res.push_str(&status);
+    }
+    res.push_str("\n\x1b[1mRecent commits:\x1b[0m\n");
+    res.push_str(&log);
+    return Ok(res);
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+
+    use test_case::test_case;
+
+    use super::{git, readme, tree, BuiltinPreview};
+    use crate::tmux::RecordingExecutor;
+
+    #[test_case("pane", BuiltinPreview::Pane)]
+    #[test_case("windows", BuiltinPreview::Windows)]
+    #[test_case(" tree ", BuiltinPreview::Tree)]
+    #[test_case("readme", BuiltinPreview::Readme)]
+    #[test_case("git", BuiltinPreview::Git)]
+    fn from_str(input: &str, expected: BuiltinPreview) {
+        assert_eq!(input.parse::<BuiltinPreview>().unwrap(), expected);
+    }
+
+    #[test]
+    fn commands_are_not_builtins() {
+        assert!("ls -la".parse::<BuiltinPreview>().is_err());
+    }
+
+    #[test]
+    fn tree_renders_nested_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/deep/deeper/deepest")).unwrap();
+        fs::create_dir_all(dir.path().join(".git/objects")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+
+        let result = tree(dir.path()).unwrap();
+        let result = result.lines().skip(1).collect::<Vec<_>>();
+
+        assert_eq!(
+            result,
+            vec![
+                "├── Cargo.toml",
+                "└── \x1b[1;34msrc\x1b[0m",
+                "    ├── \x1b[1;34mdeep\x1b[0m",
+                "    │   └── \x1b[1;34mdeeper\x1b[0m",
+                "    └── main.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn readme_prefers_shortest_name() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("README.de.md"), "german").unwrap();
+        fs::write(dir.path().join("readme.md"), "# Title\nbody").unwrap();
+
+        assert_eq!(readme(dir.path()).unwrap(), "# Title\nbody\n");
+    }
+
+    #[test]
+    fn git_shows_branch_changes_and_commits() {
+        let executor = RecordingExecutor::new()
+            .respond(&["git", "-C", "/code/proj", "rev-parse"], 0, "main\n")
+            .respond(&["git", "-C", "/code/proj", "-c"], 0, " M src/lib.rs\n")
+            .respond(&["git", "-C", "/code/proj", "log"], 0, "abc123 Initial commit\n");
+
+        let result = git(&executor, "/code/proj".as_ref()).unwrap();
+
+        assert!(result.starts_with("\x1b[1mBranch:\x1b[0m main\n"));
+        assert!(result.contains(" M src/lib.rs\n"));
+        assert!(result.ends_with("abc123 Initial commit\n"));
+        assert!(executor.calls().iter().all(|call| call[0] == "git"));
+    }
+
+    #[test]
+    fn readme_missing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(readme(dir.path()).unwrap().starts_with("No README found"));
+    }
+}
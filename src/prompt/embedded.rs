@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+use skim::{
+    prelude::{unbounded, AndOrEngineFactory, Event, ExactOrFuzzyEngineFactory, SkimOptionsBuilder},
+    CaseMatching, ItemPreview, MatchEngine, MatchEngineFactory, MatchResult, PreviewContext, Skim, SkimItem,
+    SkimItemReceiver, SkimItemSender, SkimOptions,
+};
+
+use super::{header, Picker, WINDOW_KEY};
+use crate::error::{Error, Result};
+use crate::prompt_item::Preview;
+use crate::tmux::{Execute, Tmux};
+use crate::{config::Config, prompt_item::PromptItem};
+
+/// Picks items with the embedded skim fuzzy finder.
+pub struct SkimPicker;
+
+impl Picker for SkimPicker {
+    fn pick<E, F>(&self, produce: F, tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    {
+        show(produce, tmux, config)
+    }
+}
+
+/// Item shown by skim. Skim keeps items around after the picker closed, so previews hold on to the caller's tmux.
+struct SkimPromptItem {
+    item: PromptItem,
+    preview: Arc<dyn Fn(&PromptItem) -> Preview + Send + Sync>,
+}
+
+impl SkimItem for SkimPromptItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Owned(self.item.display_text())
+    }
+
+    fn output(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.item.name)
+    }
+
+    fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        match (self.preview)(&self.item) {
+            Preview::Text(text) => ItemPreview::Text(text),
+            Preview::AnsiText(text) => ItemPreview::AnsiText(text),
+            Preview::Command(cmd) => ItemPreview::Command(cmd),
+        }
+    }
+}
+
+/// Matches like skim does by default, but ranks equally good matches by [`PromptItem::sort_key`] instead of where they
+/// matched. Skim keeps its list ordered by rank, so items are sorted while they are still streaming in.
+struct SortedEngineFactory(AndOrEngineFactory);
+
+impl MatchEngineFactory for SortedEngineFactory {
+    fn create_engine_with_case(&self, query: &str, case: CaseMatching) -> Box<dyn MatchEngine> {
+        Box::new(SortedEngine(self.0.create_engine_with_case(query, case)))
+    }
+}
+
+struct SortedEngine(Box<dyn MatchEngine>);
+
+impl MatchEngine for SortedEngine {
+    fn match_item(&self, item: Arc<dyn SkimItem>) -> Option<MatchResult> {
+        let mut result = self.0.match_item(Arc::clone(&item))?;
+        if let Some(item) = item.as_any().downcast_ref::<SkimPromptItem>() {
+            // The first part of the rank is the match score
+            result.rank[1..].copy_from_slice(&item.item.sort_key());
+        }
+        Some(result)
+    }
+}
+
+impl Display for SortedEngine {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "(Sorted {})", self.0)
+    }
+}
+
+/// Shows the picker while produce passes items to the send function it is given on a separate thread. Items show up
+/// as soon as they are sent. Sending fails once the picker closed.
+pub fn show<E, F>(produce: F, tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+where
+    E: Execute + Send + Sync + 'static,
+    F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+{
+    let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = unbounded();
+
+    let mut skim_opts = SkimOptionsBuilder::default();
+
+    let preview = &format!("right:{}%", &config.preview_width);
+
+    skim_opts.cmd_query(Some(""));
+    skim_opts.preview(Some(""));
+    skim_opts.preview_window(Some(preview));
+    skim_opts.height(Some("100%"));
+    skim_opts.multi(false);
+    skim_opts.reverse(true);
+    skim_opts.expect(Some(WINDOW_KEY.to_owned()));
+    if config.sort {
+        let engine = AndOrEngineFactory::new(ExactOrFuzzyEngineFactory::builder().build());
+        skim_opts.engine_factory(Some(Rc::new(SortedEngineFactory(engine))));
+    }
+    // skim_opts.bind(vec!["ctrl-x:execute(tmux kill-session -t {})", "ctrl-x:refresh-cmd"]);
+
+    let header = header(config.hide_banner);
+    skim_opts.header(Some(&header));
+
+    let skim_opts = skim_opts
+        .build()
+        .map_err(|err| Error::Picker(format!("Unable to build skim opts: {err}")))?;
+
+    let tmux = Arc::clone(tmux);
+    let preview: Arc<dyn Fn(&PromptItem) -> Preview + Send + Sync> = Arc::new(move |item| item.preview_content(&tmux));
+
+    return thread::scope(|scope| {
+        let producer = scope.spawn(move || {
+            produce(&mut |item| {
+                let preview = Arc::clone(&preview);
+                tx_item.send(Arc::new(SkimPromptItem { item, preview })).is_ok()
+            })
+        });
+        let selected_item = prompt_for_session(rx_item, skim_opts)?;
+        producer
+            .join()
+            .map_err(|_| Error::Picker("Listing sessions failed unexpectedly".to_owned()))??;
+        Ok(selected_item)
+    });
+}
+
+fn prompt_for_session(rx_item: SkimItemReceiver, opts: SkimOptions) -> Result<Option<PromptItem>> {
+    let (selected_items, window) = Skim::run_with(&opts, Some(rx_item))
+        .filter(|out| !out.is_abort)
+        .map(|out| {
+            let window = matches!(out.final_event, Event::EvActAccept(Some(ref key)) if key == WINDOW_KEY);
+            (out.selected_items, window)
+        })
+        .unwrap_or_default();
+
+    let selected_items = selected_items
+        .into_iter()
+        .map(|selected_item| -> Result<PromptItem> {
+            let selected = (*selected_item)
+                .as_any()
+                .downcast_ref::<SkimPromptItem>()
+                .ok_or_else(|| Error::Picker("Unable to downcast selected item to ConfigEntry".to_owned()))?;
+
+            let mut item = selected.item.to_owned();
+            item.window |= window;
+            return Ok(item);
+        })
+        .collect::<Result<Vec<PromptItem>>>();
+
+    return Ok(selected_items?.pop());
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{BufWriter, Write};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+
+use log::debug;
+
+use super::{header, Picker, WINDOW_KEY};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::prompt_item::PromptItem;
+use crate::template::shell_quote;
+use crate::tmux::{Execute, Tmux};
+
+/// Separates the name of an item from its display text in the lines fzf and sk are given.
+const DELIMITER: &str = "\t";
+
+/// Picks items with fzf or sk running as their own process, so their keybindings and themes, ie. from
+/// `$FZF_DEFAULT_OPTS`, apply as usual. Previews are shown by `tms preview`. The command is run by the shell with the
+/// arguments tms needs appended, so it may pass arguments of its own like `fzf --exact`.
+pub struct ExternalPicker {
+    command: String,
+}
+
+impl ExternalPicker {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    /// Arguments to show `<name>\t<display text>` lines like the embedded finder does, without the name. The key which
+    /// accepted the selection is printed before it.
+    fn args(&self, config: &Config) -> Vec<OsString> {
+        let tms = std::env::current_exe().map_or_else(|_| "tms".into(), |path| path.to_string_lossy().into_owned());
+        let preview = format!(
+            "{} --config {} preview {{1}}",
+            shell_quote(&tms),
+            shell_quote(&config.config_path.to_string_lossy())
+        );
+        return [
+            "--reverse",
+            "--delimiter",
+            DELIMITER,
+            "--with-nth",
+            "2..",
+            "--header",
+            &header(config.hide_banner),
+            "--preview",
+            &preview,
+            "--preview-window",
+            &format!("right:{}%", config.preview_width),
+            "--expect",
+            WINDOW_KEY,
+        ]
+        .map(OsString::from)
+        .to_vec();
+    }
+}
+
+impl Picker for ExternalPicker {
+    fn pick<E, F>(&self, produce: F, _tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("exec {} \"$@\"", self.command))
+            .arg("sh")
+            .args(self.args(config));
+        let line = |item: &PromptItem| format!("{}{DELIMITER}{}", item.name, item.display_text());
+        let name = |line: &str| line.split(DELIMITER).next().unwrap_or(line).to_owned();
+        select(cmd, &self.command, produce, line, name, true, config)
+    }
+}
+
+/// Picks items with a dmenu-style command like `rofi -dmenu` run by the shell. It is given the name of each item on a
+/// line of its stdin and prints the selected one. A name which is not listed selects a new session in `default_dir`,
+/// like `tms switch`.
+pub struct LinePicker {
+    command: String,
+}
+
+impl LinePicker {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+}
+
+impl Picker for LinePicker {
+    fn pick<E, F>(&self, produce: F, _tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(&self.command);
+        let line = |item: &PromptItem| item.name.clone();
+        let name = |line: &str| line.trim().to_owned();
+        select(cmd, &self.command, produce, line, name, false, config)
+    }
+}
+
+/// Runs the finder while produce passes items to it, writing a line per item to its stdin. The item is looked up by
+/// the name taken from the line the finder prints, following the line with the accepting key if it is told to expect
+/// keys. The finder exiting with 1, like dmenu and fzf do without a selection, or 130, when it is interrupted, means
+/// nothing was selected. Items with a tab or newline in their name are left out as the name would not survive the
+/// round trip through the finder.
+fn select<F, L, N>(
+    mut cmd: Command,
+    program: &str,
+    produce: F,
+    line: L,
+    name: N,
+    expect: bool,
+    config: &Config,
+) -> Result<Option<PromptItem>>
+where
+    F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    L: Fn(&PromptItem) -> String + Send,
+    N: Fn(&str) -> String,
+{
+    debug!("Running finder {cmd:?}");
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::spawn(program, err))?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| Error::Picker(format!("Unable to pass items to '{program}'")))?;
+
+    let (output, mut items) = thread::scope(|scope| {
+        let producer = scope.spawn(move || -> Result<HashMap<String, PromptItem>> {
+            let mut stdin = BufWriter::new(stdin);
+            let mut items = HashMap::new();
+            produce(&mut |item| {
+                if item.name.contains(DELIMITER) || item.name.contains('\n') {
+                    debug!("Leaving out {:?}, its name cannot be passed to the finder", item.name);
+                    return true;
+                }
+                // Writing fails once the finder closed
+                let open = writeln!(stdin, "{}", line(&item)).and_then(|_| stdin.flush()).is_ok();
+                items.entry(item.name.clone()).or_insert(item);
+                open
+            })?;
+            Ok(items)
+        });
+        let output = child
+            .wait_with_output()
+            .map_err(|err| Error::Picker(format!("Unable to read the selection of '{program}': {err}")));
+        let items = producer
+            .join()
+            .map_err(|_| Error::Picker("Listing sessions failed unexpectedly".to_owned()))?;
+        Ok::<_, Error>((output?, items?))
+    })?;
+
+    match output.status.code() {
+        Some(0) => {}
+        Some(1 | 130) => return Ok(None),
+        code => {
+            return Err(Error::Picker(format!(
+                "'{program}' failed{}",
+                code.map(|code| format!(" with exit code {code}")).unwrap_or_default()
+            )))
+        }
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let window = expect && lines.next() == Some(WINDOW_KEY);
+    let Some(name) = lines.next().map(name).filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    let mut item = items
+        .remove(&name)
+        .unwrap_or_else(|| PromptItem::new(name, config.default_dir.clone()));
+    item.window |= window;
+    return Ok(Some(item));
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::PathBuf;
+    use std::process::Command;
+    use std::sync::Arc;
+
+    use test_case::test_case;
+
+    use super::{select, ExternalPicker, LinePicker, DELIMITER};
+    use crate::config::{Config, Finder};
+    use crate::error::Error;
+    use crate::prompt::Picker;
+    use crate::prompt_item::PromptItem;
+    use crate::tmux::Tmux;
+
+    fn config() -> Config {
+        Config {
+            config_path: "/home/me/my config.toml".into(),
+            sort: false,
+            default_dir: PathBuf::from("/default").into(),
+            ..Default::default()
+        }
+    }
+
+    fn pick(command: &str) -> crate::Result<Option<PromptItem>> {
+        LinePicker::new(command).pick(
+            |send| {
+                for name in ["a", "b"] {
+                    send(PromptItem::new(
+                        name.to_owned(),
+                        PathBuf::from(format!("/code/{name}")).into(),
+                    ));
+                }
+                Ok(())
+            },
+            &Arc::new(Tmux::default()),
+            &config(),
+        )
+    }
+
+    #[test_case("sed -n 2p", "b", "/code/b" ; "listed item")]
+    #[test_case("cat >/dev/null; echo new", "new", "/default" ; "new session in default dir")]
+    fn selects_printed_name(command: &str, name: &str, workdir: &str) {
+        let item = pick(command).unwrap().unwrap();
+
+        assert_eq!(item.name, name);
+        assert_eq!(item.workdir.as_ref(), PathBuf::from(workdir));
+    }
+
+    #[test_case("exit 1" ; "cancelled")]
+    #[test_case("exit 130" ; "interrupted")]
+    #[test_case("true" ; "nothing printed")]
+    fn nothing_selected(command: &str) {
+        assert_eq!(pick(command).unwrap(), None);
+    }
+
+    #[test]
+    fn finder_picks_with_configured_command() {
+        let finder = Finder::Lines("sed -n 1p".to_owned());
+
+        let item = finder
+            .pick(
+                |send| {
+                    send(PromptItem::new("a".to_owned(), PathBuf::from("/code/a").into()));
+                    Ok(())
+                },
+                &Arc::new(Tmux::default()),
+                &config(),
+            )
+            .unwrap();
+
+        assert_eq!(item.map(|item| item.name).as_deref(), Some("a"));
+    }
+
+    #[cfg(not(feature = "skim"))]
+    #[test]
+    fn skim_finder_needs_the_feature() {
+        let res = Finder::Skim.pick(|_| Ok(()), &Arc::new(Tmux::default()), &config());
+
+        assert!(matches!(res, Err(Error::Picker(message)) if message.starts_with("tms was built without skim")));
+    }
+
+    #[test_case("printf 'ctrl-o\\nb\\n'", true ; "window key")]
+    #[test_case("printf '\\nb\\n'", false ; "enter")]
+    fn expected_key_precedes_selection(script: &str, window: bool) {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(format!("cat >/dev/null; {script}"));
+        let produce = |send: &mut dyn FnMut(PromptItem) -> bool| {
+            send(PromptItem::new("b".to_owned(), PathBuf::from("/code/b").into()));
+            Ok(())
+        };
+
+        let item = select(
+            cmd,
+            "sh",
+            produce,
+            |item| item.name.clone(),
+            str::to_owned,
+            true,
+            &config(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!((item.name.as_str(), item.window), ("b", window));
+    }
+
+    #[test]
+    fn names_with_tab_or_newline_are_left_out() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("sed -n 1p");
+        let produce = |send: &mut dyn FnMut(PromptItem) -> bool| {
+            for name in ["tab\there", "new\nline", "plain"] {
+                send(PromptItem::new(name.to_owned(), PathBuf::from("/code").into()));
+            }
+            Ok(())
+        };
+
+        let item = select(
+            cmd,
+            "sh",
+            produce,
+            |item| format!("{}{DELIMITER}{}", item.name, item.display_text()),
+            |line| line.split(DELIMITER).next().unwrap_or(line).to_owned(),
+            false,
+            &config(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(item.name, "plain");
+        assert_eq!(item.workdir.as_ref(), PathBuf::from("/code"));
+    }
+
+    #[test]
+    fn failing_finder_is_an_error() {
+        assert!(matches!(pick("exit 2"), Err(Error::Picker(message)) if message.ends_with("exit code 2")));
+    }
+
+    #[test]
+    fn external_finder_keeps_its_arguments() {
+        // Prints no key and the selection passed as the first argument
+        let command = r#"sh -c 'cat >/dev/null; printf "\n%s\n" "$1"' sh b"#;
+
+        let item = ExternalPicker::new(command)
+            .pick(
+                |send| {
+                    for name in ["a", "b"] {
+                        send(PromptItem::new(
+                            name.to_owned(),
+                            PathBuf::from(format!("/code/{name}")).into(),
+                        ));
+                    }
+                    Ok(())
+                },
+                &Arc::new(Tmux::default()),
+                &config(),
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(item.workdir.as_ref(), PathBuf::from("/code/b"));
+    }
+
+    #[test]
+    fn external_finder_previews_with_tms() {
+        let args = ExternalPicker::new("fzf").args(&config());
+        let args = args.iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>();
+
+        let preview = args.iter().position(|arg| arg == "--preview").unwrap() + 1;
+        assert!(args[preview].ends_with(" --config '/home/me/my config.toml' preview {1}"));
+        assert!(args.windows(2).any(|pair| pair == ["--with-nth", "2.."]));
+        assert!(args.windows(2).any(|pair| pair == ["--preview-window", "right:30%"]));
+        assert!(args.windows(2).any(|pair| pair == ["--expect", "ctrl-o"]));
+    }
+}
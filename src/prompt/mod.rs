@@ -0,0 +1,78 @@
+#[cfg(feature = "skim")]
+mod embedded;
+mod external;
+
+#[cfg(feature = "skim")]
+pub use self::embedded::{show, SkimPicker};
+pub use self::external::{ExternalPicker, LinePicker};
+
+use std::sync::Arc;
+
+use crate::config::{Config, Finder};
+use crate::error::Result;
+use crate::prompt_item::PromptItem;
+use crate::tmux::{Execute, Tmux};
+
+#[rustfmt::skip]
+static HEADER: &str = concat!(
+    r#" /$$$$$$$$ /$$      /$$ /$$   /$$ /$$   /$$        /$$$$$$  /$$$$$$$$  /$$$$$$   /$$$$$$  /$$$$$$  /$$$$$$  /$$   /$$ /$$$$$$ /$$$$$$$$ /$$$$$$$$ /$$$$$$$ "#, "\n",
+    r#"|__  $$__/| $$$    /$$$| $$  | $$| $$  / $$       /$$__  $$| $$_____/ /$$__  $$ /$$__  $$|_  $$_/ /$$__  $$| $$$ | $$|_  $$_/|_____ $$ | $$_____/| $$__  $$"#, "\n",
+    r#"   | $$   | $$$$  /$$$$| $$  | $$|  $$/ $$/      | $$  \__/| $$      | $$  \__/| $$  \__/  | $$  | $$  \ $$| $$$$| $$  | $$       /$$/ | $$      | $$  \ $$"#, "\n",
+    r#"   | $$   | $$ $$/$$ $$| $$  | $$ \  $$$$/       |  $$$$$$ | $$$$$   |  $$$$$$ |  $$$$$$   | $$  | $$  | $$| $$ $$ $$  | $$      /$$/  | $$$$$   | $$$$$$$/"#, "\n",
+    r#"   | $$   | $$  $$$| $$| $$  | $$  >$$  $$        \____  $$| $$__/    \____  $$ \____  $$  | $$  | $$  | $$| $$  $$$$  | $$     /$$/   | $$__/   | $$__  $$"#, "\n",
+    r#"   | $$   | $$\  $ | $$| $$  | $$ /$$/\  $$       /$$  \ $$| $$       /$$  \ $$ /$$  \ $$  | $$  | $$  | $$| $$\  $$$  | $$    /$$/    | $$      | $$  \ $$"#, "\n",
+    r#"   | $$   | $$ \/  | $$|  $$$$$$/| $$  \ $$      |  $$$$$$/| $$$$$$$$|  $$$$$$/|  $$$$$$/ /$$$$$$|  $$$$$$/| $$ \  $$ /$$$$$$ /$$$$$$$$| $$$$$$$$| $$  | $$"#, "\n",
+    r#"   |__/   |__/     |__/ \______/ |__/  |__/       \______/ |________/ \______/  \______/ |______/ \______/ |__/  \__/|______/|________/|________/|__/  |__/"#, "\n"
+);
+
+/// Lets the user pick one of the items produce passes to the send function it is given. Builtin previews are rendered
+/// with the server of tmux.
+pub trait Picker {
+    fn pick<E, F>(&self, produce: F, tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send;
+}
+
+/// Picks with the finder configured in `finder`.
+impl Picker for Finder {
+    fn pick<E, F>(&self, produce: F, tmux: &Arc<Tmux<E>>, config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    {
+        match self {
+            #[cfg(feature = "skim")]
+            Finder::Skim => SkimPicker.pick(produce, tmux, config),
+            #[cfg(not(feature = "skim"))]
+            Finder::Skim => {
+                drop(produce);
+                Err(crate::Error::Picker(SKIM_MISSING.to_owned()))
+            }
+            Finder::External(command) => ExternalPicker::new(command).pick(produce, tmux, config),
+            Finder::Lines(command) => LinePicker::new(command).pick(produce, tmux, config),
+        }
+    }
+}
+
+/// Key accepting the selection in skim, fzf and sk to open it as a window in the current session.
+pub(crate) const WINDOW_KEY: &str = "ctrl-o";
+
+/// Why the skim finder cannot be used in builds without the `skim` feature.
+pub(crate) const SKIM_MISSING: &str =
+    "tms was built without skim, set 'finder' to \"fzf\", \"sk\" or a dmenu-style command instead";
+
+/// Banner, unless hidden, followed by the titles of the columns of [`PromptItem::display_text`].
+pub fn header(hide_banner: bool) -> String {
+    let mut header = if hide_banner {
+        String::new()
+    } else {
+        String::from(HEADER)
+    };
+    header.push_str(&format!(
+        "{:^3} {:^40} {:^60} {}",
+        "*", "Name", "Working Directory", "Window Count",
+    ));
+
+    return header;
+}
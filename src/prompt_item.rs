@@ -1,96 +1,322 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::fs;
+use std::collections::{HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Instant;
 
-use anyhow::Context;
-use anyhow::Result;
-use skim::ItemPreview;
-use skim::SkimItem;
+use log::debug;
 
 use crate::config::Config;
 use crate::config::Entry;
 use crate::config::EntryDir;
 use crate::config::EntryPlain;
+use crate::config::EntrySsh;
+use crate::config::EntryZoxide;
 use crate::config::PreviewCommands;
 use crate::config::Workdir;
+use crate::error::{Context, Result};
+use crate::preview::BuiltinPreview;
+use crate::template::shell_quote;
+use crate::template::Escape;
+use crate::template::Template;
+use crate::tmux::Execute;
 use crate::tmux::SessionStats;
 use crate::tmux::Sessions;
-use crate::utils::is_dir;
+use crate::tmux::Tmux;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PromptItem {
     pub name: String,
     pub workdir: Workdir,
     pub stats: Option<SessionStats>,
+    pub command: Option<String>,
+    /// Whether the item opens as a window in the current session instead of as a session
+    pub window: bool,
     preview_cmd: Option<PreviewCommands>,
+    /// Root of the entry the item was found in
+    root: Option<Workdir>,
 }
 
 impl Entry {
-    fn into_prompt_items<F: FnMut(PromptItem)>(self, sessions: &Sessions, for_each: F) -> Result<()> {
+    /// Passes the items of the entry to for_each until it returns false.
+    fn into_prompt_items<E: Execute, F: FnMut(PromptItem) -> bool>(
+        self,
+        config: &Config,
+        sessions: &Sessions,
+        executor: &E,
+        for_each: F,
+    ) -> Result<()> {
         match self {
-            Entry::Dir(e) => e.into_prompt_items(sessions, for_each),
+            Entry::Dir(e) => e.into_prompt_items(sessions, config.cache_dir.as_deref(), for_each),
             Entry::Plain(e) => e.into_prompt_items(sessions, for_each),
+            Entry::Ssh(e) => e.into_prompt_items(sessions, for_each),
+            Entry::Zoxide(e) => e.into_prompt_items(sessions, executor, for_each),
+            Entry::Workspace(e) => e.into_workspace_prompt_items(sessions, for_each),
+            Entry::GitSubmodules(e) => e.into_submodule_prompt_items(sessions, for_each),
         }
     }
 }
 
+impl Entry {
+    /// Item of the entry called name. Dir entries which were scanned before only look at their cached dirs, the cache
+    /// is up to date for anything the picker showed.
+    fn find_prompt_item<E: Execute>(
+        self,
+        config: &Config,
+        sessions: &Sessions,
+        executor: &E,
+        name: &str,
+    ) -> Result<Option<PromptItem>> {
+        if let (Entry::Dir(e), Some(cache_dir)) = (&self, config.cache_dir.as_deref()) {
+            if let Some(dirs) = crate::cache::cached(cache_dir, e.workdir.as_ref(), &e.scan) {
+                return e.find_cached_prompt_item(sessions, dirs, name);
+            }
+        }
+
+        let mut found = None;
+        self.into_prompt_items(config, sessions, executor, |item| {
+            if item.name != name {
+                return true;
+            }
+            found = Some(item);
+            false
+        })?;
+        return Ok(found);
+    }
+}
+
 impl SessionStats {
-    fn into_prompt_items<F: FnMut(PromptItem)>(self, name: String, workdir: Workdir, mut for_each: F) -> Result<()> {
+    fn into_prompt_items<F: FnMut(PromptItem)>(self, name: String, config: &Config, mut for_each: F) -> Result<()> {
         for_each(PromptItem {
-            workdir,
+            workdir: config.default_dir.to_owned(),
             name,
             stats: Some(self),
-            preview_cmd: None,
+            command: None,
+            window: false,
+            preview_cmd: config.preview_commands.to_owned(),
+            root: None,
         });
         Ok(())
     }
 }
 
 impl EntryDir {
-    fn into_prompt_items<F: FnMut(PromptItem)>(self, sessions: &Sessions, mut for_each: F) -> Result<()> {
-        fs::read_dir(self.workdir.as_ref())
-            .context(format!("Unable to read dir '{}'.", self.workdir.as_ref()))?
-            .filter(is_dir)
-            .try_for_each(|entry| -> Result<()> {
-                let entry = entry.context("Unexpected error when reading dir.")?;
-
-                let dir_path = entry
-                    .path()
-                    .to_str()
-                    .context("Unable to convert path {:?} to str.")?
-                    .to_owned();
-
-                let name = dir_path
-                    .split('/')
-                    .last()
-                    .context("Unable to convert path {:?} to str.")?
-                    .to_owned();
-
-                if self.excludes.as_ref().map_or(false, |v| v.iter().any(|el| *el == name)) {
-                    return Ok(());
+    fn into_prompt_items<F: FnMut(PromptItem) -> bool>(
+        self,
+        sessions: &Sessions,
+        cache_dir: Option<&Path>,
+        mut for_each: F,
+    ) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        let workdir: &Path = self.workdir.as_ref();
+        let mut found = |dir_path: PathBuf| -> Result<bool> {
+            match self.dir_prompt_item(&template, &excludes, sessions, dir_path)? {
+                Some(prompt_item) => Ok(for_each(prompt_item)),
+                None => Ok(true),
+            }
+        };
+
+        match cache_dir {
+            Some(cache_dir) => crate::cache::subdirs(cache_dir, workdir, &self.scan, found),
+            None => {
+                for dir_path in crate::scan::subdirs(workdir, &self.scan)? {
+                    if !found(dir_path)? {
+                        break;
+                    }
                 }
+                Ok(())
+            }
+        }
+    }
 
-                let mut prompt_item = PromptItem::new(name, dir_path.try_into()?);
-                prompt_item.preview_cmd = self.preview_cmd.to_owned();
-                prompt_item.name = self
-                    .name
-                    .replace("{{workdir}}", self.workdir.as_ref())
-                    .replace("{{name}}", &prompt_item.name);
-                prompt_item.populate_session_data(sessions);
+    /// Passes the items of the cached dirs of the entry to for_each until it returns false, without revalidating the
+    /// cache. Returns the cached dirs, or None if the entry was not scanned yet.
+    fn cached_prompt_items<F: FnMut(PromptItem) -> bool>(
+        &self,
+        sessions: &Sessions,
+        cache_dir: &Path,
+        mut for_each: F,
+    ) -> Result<Option<Vec<PathBuf>>> {
+        let Some(dirs) = crate::cache::cached(cache_dir, self.workdir.as_ref(), &self.scan) else {
+            return Ok(None);
+        };
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for dir_path in &dirs {
+            let prompt_item = self.dir_prompt_item(&template, &excludes, sessions, dir_path.clone())?;
+            if prompt_item.is_some_and(|prompt_item| !for_each(prompt_item)) {
+                break;
+            }
+        }
+        return Ok(Some(dirs));
+    }
 
-                for_each(prompt_item);
+    /// Revalidates the cache of the entry, passing the items of dirs which are not among the listed ones to for_each
+    /// until it returns false.
+    fn revalidated_prompt_items<F: FnMut(PromptItem) -> bool>(
+        &self,
+        sessions: &Sessions,
+        cache_dir: &Path,
+        listed: &[PathBuf],
+        mut for_each: F,
+    ) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        crate::cache::revalidate(
+            cache_dir,
+            self.workdir.as_ref(),
+            &self.scan,
+            listed,
+            |dir_path| match self.dir_prompt_item(&template, &excludes, sessions, dir_path)? {
+                Some(prompt_item) => Ok(for_each(prompt_item)),
+                None => Ok(true),
+            },
+        )
+    }
 
-                Ok(())
-            })?;
+    /// Item called name among the cached dirs of the entry.
+    fn find_cached_prompt_item(
+        &self,
+        sessions: &Sessions,
+        dirs: Vec<PathBuf>,
+        name: &str,
+    ) -> Result<Option<PromptItem>> {
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for dir_path in dirs {
+            let prompt_item = self.dir_prompt_item(&template, &excludes, sessions, dir_path)?;
+            if let Some(prompt_item) = prompt_item.filter(|item| item.name == name) {
+                return Ok(Some(prompt_item));
+            }
+        }
+        return Ok(None);
+    }
+
+    /// Item of a subdirectory of the entry, unless it is excluded.
+    fn dir_prompt_item(
+        &self,
+        template: &Template,
+        excludes: &Excludes,
+        sessions: &Sessions,
+        dir_path: PathBuf,
+    ) -> Result<Option<PromptItem>> {
+        let file_name = dir_path.file_name().unwrap_or(dir_path.as_os_str());
+        if excludes.matches(file_name) {
+            return Ok(None);
+        }
+
+        let workdir: &Path = self.workdir.as_ref();
+        let name = file_name.to_string_lossy();
+        let placeholders = Placeholders {
+            name: Some(&name),
+            workdir,
+            dir: &dir_path,
+            root: Some(workdir),
+            package: None,
+        };
+        let name = placeholders.render(template, Escape::None)?;
+        let mut prompt_item = PromptItem::new(name, dir_path.into());
+        prompt_item.root = Some(self.workdir.clone());
+        prompt_item.preview_cmd = self.preview_cmd.to_owned();
+        prompt_item.window = self.window;
+        prompt_item.populate_session_data(sessions);
+
+        return Ok(Some(prompt_item));
+    }
+
+    fn into_workspace_prompt_items<F: FnMut(PromptItem) -> bool>(
+        self,
+        sessions: &Sessions,
+        mut for_each: F,
+    ) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let workdir: &Path = self.workdir.as_ref();
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for member in crate::workspace::read_members(workdir)? {
+            let file_name = member.path.file_name().unwrap_or(member.path.as_os_str());
+            let name = file_name.to_string_lossy();
+            let package = member.package.as_deref().unwrap_or(&name);
+
+            if excludes.matches(file_name) || excludes.matches(package.as_ref()) {
+                continue;
+            }
+
+            let placeholders = Placeholders {
+                name: Some(&name),
+                workdir,
+                dir: &member.path,
+                root: Some(workdir),
+                package: Some(package),
+            };
+            let name = placeholders.render(&template, Escape::None)?;
+            let mut prompt_item = PromptItem::new(name, member.path.clone().into());
+            prompt_item.root = Some(self.workdir.clone());
+            prompt_item.preview_cmd = self.preview_cmd.to_owned();
+            prompt_item.window = self.window;
+            prompt_item.populate_session_data(sessions);
+
+            if !for_each(prompt_item) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn into_submodule_prompt_items<F: FnMut(PromptItem) -> bool>(
+        self,
+        sessions: &Sessions,
+        mut for_each: F,
+    ) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let workdir: &Path = self.workdir.as_ref();
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for submodule in crate::git::read_submodules(workdir)? {
+            if excludes.matches(submodule.name.as_ref()) {
+                continue;
+            }
+
+            let placeholders = Placeholders {
+                name: Some(&submodule.name),
+                workdir,
+                dir: &submodule.path,
+                root: Some(workdir),
+                package: None,
+            };
+            let name = placeholders.render(&template, Escape::None)?;
+            let mut prompt_item = PromptItem::new(name, submodule.path.into());
+            prompt_item.root = Some(self.workdir.clone());
+            prompt_item.preview_cmd = self.preview_cmd.to_owned();
+            prompt_item.window = self.window;
+            prompt_item.populate_session_data(sessions);
+
+            if !for_each(prompt_item) {
+                break;
+            }
+        }
 
         Ok(())
     }
 }
 impl EntryPlain {
-    fn into_prompt_items<F: FnMut(PromptItem)>(self, sessions: &Sessions, mut for_each: F) -> Result<()> {
-        let mut prompt_item = PromptItem::new(self.name, self.workdir);
+    fn into_prompt_items<F: FnMut(PromptItem) -> bool>(self, sessions: &Sessions, mut for_each: F) -> Result<()> {
+        let placeholders = Placeholders {
+            name: None,
+            workdir: self.workdir.as_ref(),
+            dir: self.workdir.as_ref(),
+            root: None,
+            package: None,
+        };
+        let name = placeholders.render(&Template::parse(&self.name)?, Escape::None)?;
+        let mut prompt_item = PromptItem::new(name, self.workdir);
         prompt_item.populate_session_data(sessions);
         prompt_item.preview_cmd = self.preview_cmd;
+        prompt_item.window = self.window;
 
         for_each(prompt_item);
 
@@ -98,13 +324,151 @@ impl EntryPlain {
     }
 }
 
+impl EntrySsh {
+    fn into_prompt_items<F: FnMut(PromptItem) -> bool>(self, sessions: &Sessions, mut for_each: F) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for host in crate::ssh_config::read_hosts(&self.ssh_config)? {
+            if excludes.matches(host.as_ref()) {
+                continue;
+            }
+
+            let placeholders = Placeholders {
+                name: Some(&host),
+                workdir: self.workdir.as_ref(),
+                dir: self.workdir.as_ref(),
+                root: None,
+                package: None,
+            };
+            let mut prompt_item = PromptItem::new(placeholders.render(&template, Escape::None)?, self.workdir.clone());
+            prompt_item.command = Some(format!("ssh {}", shell_quote(&host)));
+            prompt_item.preview_cmd = self.preview_cmd.to_owned();
+            prompt_item.window = self.window;
+            prompt_item.populate_session_data(sessions);
+
+            if !for_each(prompt_item) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl EntryZoxide {
+    fn into_prompt_items<E: Execute, F: FnMut(PromptItem) -> bool>(
+        self,
+        sessions: &Sessions,
+        executor: &E,
+        mut for_each: F,
+    ) -> Result<()> {
+        let template = Template::parse(&self.name)?;
+        let excludes = Excludes::new(self.excludes.as_deref())?;
+        for dir in crate::zoxide::read_dirs(&self, executor)? {
+            let file_name = dir.file_name().unwrap_or(dir.as_os_str());
+            if excludes.matches(file_name) {
+                continue;
+            }
+
+            let name = file_name.to_string_lossy();
+            let placeholders = Placeholders {
+                name: Some(&name),
+                workdir: &dir,
+                dir: &dir,
+                root: None,
+                package: None,
+            };
+            let name = placeholders.render(&template, Escape::None)?;
+            let mut prompt_item = PromptItem::new(name, dir.into());
+            prompt_item.preview_cmd = self.preview_cmd.to_owned();
+            prompt_item.window = self.window;
+            prompt_item.populate_session_data(sessions);
+
+            if !for_each(prompt_item) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Names excluded from an entry, either literally or by glob pattern, ie. `build-*`.
+struct Excludes(Vec<glob::Pattern>);
+
+impl Excludes {
+    fn new(excludes: Option<&[String]>) -> Result<Self> {
+        excludes
+            .unwrap_or_default()
+            .iter()
+            .map(|exclude| glob::Pattern::new(exclude).config_context(format!("Invalid exclude pattern '{exclude}'.")))
+            .collect::<Result<Vec<_>>>()
+            .map(Self)
+    }
+
+    fn matches(&self, name: &OsStr) -> bool {
+        let name = name.to_string_lossy();
+        self.0
+            .iter()
+            .any(|pattern| pattern.as_str() == name || pattern.matches(&name))
+    }
+}
+
+/// Values of template placeholders for a single item. Entry names are not passed through a shell so they are
+/// rendered with [`Escape::None`], preview commands with [`Escape::Shell`].
+struct Placeholders<'a> {
+    name: Option<&'a str>,
+    /// Value of `{{workdir}}`, which is the entry root for entries listing multiple directories
+    workdir: &'a Path,
+    /// Directory of the item itself
+    dir: &'a Path,
+    /// Root `{{relpath}}` is relative to
+    root: Option<&'a Path>,
+    package: Option<&'a str>,
+}
+
+impl<'a> Placeholders<'a> {
+    fn lookup(&self, key: &str) -> Option<Cow<'a, str>> {
+        let value = match key {
+            "name" => Cow::Borrowed(self.name?),
+            "workdir" => self.workdir.to_string_lossy(),
+            "package" => Cow::Borrowed(self.package?),
+            "basename" => self
+                .dir
+                .file_name()
+                .map_or(Cow::Borrowed(""), |name| name.to_string_lossy()),
+            "parent" => self
+                .dir
+                .parent()
+                .map_or(Cow::Borrowed(""), |parent| parent.to_string_lossy()),
+            "relpath" => match self.root.and_then(|root| self.dir.strip_prefix(root).ok()) {
+                Some(relpath) => relpath.to_string_lossy(),
+                None => self.dir.to_string_lossy(),
+            },
+            "home_relative" => Cow::Owned(crate::utils::home_relative(self.dir)),
+            "git_branch" => Cow::Owned(crate::git::branch(self.dir).unwrap_or_default()),
+            "git_remote_repo" => Cow::Owned(crate::git::remote_repo(self.dir).unwrap_or_default()),
+            "hostname" => Cow::Owned(crate::utils::hostname()),
+            _ => return None,
+        };
+        return Some(value);
+    }
+
+    fn render(&self, template: &Template, escape: Escape) -> Result<String> {
+        template.render(escape, |key| self.lookup(key))
+    }
+}
+
 impl PromptItem {
     pub fn new(name: String, workdir: Workdir) -> Self {
         return PromptItem {
             name,
             workdir,
+            command: None,
+            window: false,
             preview_cmd: None,
             stats: None,
+            root: None,
         };
     }
 
@@ -113,94 +477,636 @@ impl PromptItem {
             self.stats = Some(s.to_owned());
         }
     }
-}
 
-impl SkimItem for PromptItem {
-    fn text(&self) -> Cow<str> {
+    /// Key ordering items the way `sort` does, for finders which keep items sorted while they stream in. Names are
+    /// only compared on their first 10 bytes, items which are equal up to there keep the order they are passed in.
+    pub fn sort_key(&self) -> [i32; 3] {
+        let (rank, name) = match self.stats {
+            Some(SessionStats { attached: true, .. }) => (0, String::new()),
+            Some(SessionStats { window_count, .. }) => (256 - u128::from(window_count), String::new()),
+            None => (257, self.name.to_lowercase()),
+        };
+        let mut name_bytes = [0; 16];
+        for (byte, name_byte) in name_bytes[6..].iter_mut().zip(name.bytes()) {
+            *byte = name_byte;
+        }
+        let key = rank << 80 | u128::from_be_bytes(name_bytes);
+
+        // Flipping the sign bit keeps the order of the unsigned parts
+        return [key >> 64, key >> 32, key].map(|part| (part as u32 ^ 0x8000_0000) as i32);
+    }
+
+    /// Line the item is shown as in the finder, in columns matching [`crate::prompt::header`].
+    pub fn display_text(&self) -> String {
         match self.stats {
-            Some(ref stats) => Cow::Owned(format!(
+            Some(ref stats) => format!(
                 "{:<3} {:<40} {:<60} {}",
                 if stats.attached { "(*)" } else { "( )" },
                 self.name,
-                self.workdir.as_ref(),
+                self.workdir.as_ref().to_string_lossy(),
                 format_args!("{} window(s)", stats.window_count)
-            )),
-            None => Cow::Owned(format!(
+            ),
+            None => format!(
                 "{:<3} {:<40} {:<60} {}",
                 "",
                 self.name,
-                self.workdir.as_ref(),
+                self.workdir.as_ref().to_string_lossy(),
                 ""
-            )),
+            ),
         }
     }
+}
 
-    fn output(&self) -> Cow<str> {
-        Cow::Borrowed(&self.name)
-    }
+/// What the preview of an item shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Preview {
+    Text(String),
+    /// Text containing ANSI escape sequences
+    AnsiText(String),
+    /// Shell command whose output is shown
+    Command(String),
+}
 
-    fn preview(&self, _context: skim::PreviewContext) -> skim::ItemPreview {
+impl PromptItem {
+    /// Preview of the item, using the preview command for running or not running sessions depending on whether the
+    /// session is running. Builtin previews query the server of tmux. Errors are shown as the preview text.
+    pub fn preview_content<E: Execute>(&self, tmux: &Tmux<E>) -> Preview {
         let session_running = self.stats.is_some();
 
-        return match &self.preview_cmd {
+        let cmd = match &self.preview_cmd {
             Some(PreviewCommands {
                 running: Some(running), ..
-            }) if session_running => ItemPreview::Command(
-                running
-                    .replace("{{workdir}}", self.workdir.as_ref())
-                    .replace("{{name}}", &self.name),
-            ),
+            }) if session_running => running,
             Some(PreviewCommands {
                 not_running: Some(not_running),
                 ..
-            }) if !session_running => ItemPreview::Command(
-                not_running
-                    .replace("{{workdir}}", self.workdir.as_ref())
-                    .replace("{{name}}", &self.name),
-            ),
-            _ => ItemPreview::Text("".to_owned()),
+            }) if !session_running => not_running,
+            _ => return Preview::Text("".to_owned()),
+        };
+
+        return match cmd.parse::<BuiltinPreview>() {
+            Ok(builtin) => match builtin.render(tmux, &self.name, self.workdir.as_ref()) {
+                Ok(text) => Preview::AnsiText(text),
+                Err(err) => Preview::Text(format!("{err:#}")),
+            },
+            Err(_) => match Template::parse(cmd).and_then(|template| {
+                Placeholders {
+                    name: Some(&self.name),
+                    workdir: self.workdir.as_ref(),
+                    dir: self.workdir.as_ref(),
+                    root: self.root.as_ref().map(|root| root.as_ref()),
+                    package: None,
+                }
+                .render(&template, Escape::Shell)
+            }) {
+                Ok(cmd) => Preview::Command(cmd),
+                Err(err) => Preview::Text(format!("{err:#}")),
+            },
         };
     }
 }
 
 pub trait IntoPromptItems {
-    fn into_prompt_items(self, config: &Config, active_sessions: Sessions) -> Result<Vec<PromptItem>>;
+    fn into_prompt_items<E: Execute + Sync>(
+        self,
+        config: &Config,
+        active_sessions: Sessions,
+        executor: &E,
+    ) -> Result<Vec<PromptItem>>;
 }
 
 impl IntoPromptItems for Vec<Entry> {
-    fn into_prompt_items(self, config: &Config, mut sessions: Sessions) -> Result<Vec<PromptItem>> {
-        let mut res = self.into_iter().try_fold(Vec::new(), |mut acc, e| {
-            e.into_prompt_items(&sessions, |item| acc.push(item))?;
-            Ok::<Vec<PromptItem>, anyhow::Error>(acc)
+    fn into_prompt_items<E: Execute + Sync>(
+        self,
+        config: &Config,
+        sessions: Sessions,
+        executor: &E,
+    ) -> Result<Vec<PromptItem>> {
+        let mut res = Vec::new();
+        stream_prompt_items(self, config, sessions, executor, |item| {
+            res.push(item);
+            true
         })?;
 
-        for ele in res.iter() {
-            sessions.value_ref_mut().remove(&ele.name);
+        if config.sort {
+            res.sort_by(compare);
         }
 
-        if !sessions.value_ref().is_empty() {
-            for (k, v) in sessions.value().into_iter() {
-                v.into_prompt_items(k.to_owned(), config.default_dir.to_owned(), |item| res.push(item))?;
+        return Ok(res);
+    }
+}
+
+enum Message {
+    Item(usize, PromptItem),
+    /// Items passed so far are all the entry lists without revalidating its cache
+    Listed(usize),
+    Done(usize, Result<()>),
+}
+
+/// Work of the workers of [`stream_prompt_items`], each with the index of its entry.
+enum Job {
+    List(usize, Entry),
+    /// Revalidates the cache of a dir entry whose cached dirs were listed
+    Revalidate(usize, EntryDir, Vec<PathBuf>),
+}
+
+impl Job {
+    /// Passes the items of the job to send until it returns false. Listing a dir entry with cached dirs returns the
+    /// job revalidating them, so it can run without holding up other entries.
+    fn run<E: Execute, F: FnMut(PromptItem) -> bool>(
+        self,
+        config: &Config,
+        sessions: &Sessions,
+        executor: &E,
+        mut send: F,
+    ) -> Result<Option<Job>> {
+        let cache_dir = config.cache_dir.as_deref();
+        match (self, cache_dir) {
+            (Job::List(idx, Entry::Dir(e)), Some(cache_dir)) => {
+                match e.cached_prompt_items(sessions, cache_dir, &mut send)? {
+                    Some(dirs) => Ok(Some(Job::Revalidate(idx, e, dirs))),
+                    None => e.into_prompt_items(sessions, Some(cache_dir), send).map(|_| None),
+                }
             }
+            (Job::List(_, entry), _) => entry.into_prompt_items(config, sessions, executor, send).map(|_| None),
+            (Job::Revalidate(_, e, dirs), Some(cache_dir)) => e
+                .revalidated_prompt_items(sessions, cache_dir, &dirs, send)
+                .map(|_| None),
+            (Job::Revalidate(..), None) => Ok(None),
         }
+    }
 
-        if config.sort {
-            #[rustfmt::skip]
-            res.sort_by(|a, b| {
-                match (a, b) {
-                    (PromptItem { stats: Some(SessionStats { attached: true, ..}), .. }, _) => Ordering::Less,
-                    (_, PromptItem { stats: Some(SessionStats { attached: true, ..}), .. }) => Ordering::Greater,
-                    (PromptItem { stats: Some(_), .. }, PromptItem { stats: None, .. }) => Ordering::Less,
-                    (PromptItem { stats: None, .. }, PromptItem { stats: Some(_), .. }) => Ordering::Greater,
-                    (PromptItem { stats: Some(SessionStats { window_count: c1, .. }), .. }, PromptItem { stats: Some(SessionStats { window_count: c2, .. }), .. }) if (c1 == c2) => Ordering::Equal,
-                    (PromptItem { stats: Some(SessionStats { window_count: c1, .. }), .. }, PromptItem { stats: Some(SessionStats { window_count: c2, .. }), .. }) if (c1 > c2) => Ordering::Less,
-                    (PromptItem { stats: Some(_), .. }, PromptItem { stats: Some(_), .. }) => Ordering::Greater,
-                    (PromptItem { name: name1, .. }, PromptItem { name: name2, .. }) => name1.to_lowercase().cmp(&name2.to_lowercase()),
+    fn idx(&self) -> usize {
+        match self {
+            Job::List(idx, _) | Job::Revalidate(idx, ..) => *idx,
+        }
+    }
 
+    fn describe(&self) -> String {
+        match self {
+            Job::List(_, entry) => format!("Listed entry '{}'", entry.name()),
+            Job::Revalidate(_, e, _) => format!("Revalidated entry '{}'", e.name),
+        }
+    }
+}
+
+/// Item called name among the items of entries and running sessions, the way [`stream_prompt_items`] lists them.
+/// `None` if neither has it.
+///
+/// Entries are listed one after the other until one has the item, so a single preview does not scan everything.
+pub fn find_prompt_item<E: Execute>(
+    entries: Vec<Entry>,
+    config: &Config,
+    sessions: Sessions,
+    executor: &E,
+    name: &str,
+) -> Result<Option<PromptItem>> {
+    for entry in entries {
+        if let Some(item) = entry.find_prompt_item(config, &sessions, executor, name)? {
+            return Ok(Some(item));
+        }
+    }
+
+    if let Some(stats) = sessions.value_ref().get(name) {
+        let mut found = None;
+        stats
+            .to_owned()
+            .into_prompt_items(name.to_owned(), config, |item| found = Some(item))?;
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+
+    return Ok(None);
+}
+
+/// Generates the prompt items of all entries on a pool of worker threads and passes each one to send as soon as it
+/// can be shown. Running sessions which no entry matches are passed last. Stops early when send returns false, entries
+/// which are still being listed then stop as well. Cached dirs are passed right away and revalidated as a separate job
+/// once they are listed.
+///
+/// Without sorting items keep the order of their entries, so items of an entry are held back until all earlier
+/// entries are listed. With sorting items are passed as soon as they are found and the picker orders them, see
+/// [`PromptItem::sort_key`].
+pub fn stream_prompt_items<E, F>(
+    entries: Vec<Entry>,
+    config: &Config,
+    sessions: Sessions,
+    executor: &E,
+    mut send: F,
+) -> Result<()>
+where
+    E: Execute + Sync,
+    F: FnMut(PromptItem) -> bool,
+{
+    let entry_count = entries.len();
+    let queue = Mutex::new(
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(idx, entry)| Job::List(idx, entry))
+            .collect::<VecDeque<_>>(),
+    );
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(entry_count);
+    let (tx, rx) = mpsc::channel();
+
+    let mut seen = HashSet::new();
+    let mut forward = |item: PromptItem| -> bool {
+        seen.insert(item.name.clone());
+        send(item)
+    };
+
+    let completed = thread::scope(|scope| -> Result<bool> {
+        for _ in 0..workers {
+            let (tx, queue, sessions) = (tx.clone(), &queue, &sessions);
+            scope.spawn(move || {
+                while let Some(job) = queue.lock().ok().and_then(|mut queue| queue.pop_front()) {
+                    let (idx, what, start) = (job.idx(), job.describe(), Instant::now());
+                    let (mut open, mut count) = (true, 0);
+                    let res = job.run(config, sessions, executor, |item| {
+                        count += 1;
+                        open = tx.send(Message::Item(idx, item)).is_ok();
+                        open
+                    });
+                    debug!("{what} with {count} items in {:.1?}", start.elapsed());
+                    if !open {
+                        return;
+                    }
+                    let message = match res {
+                        Ok(Some(revalidate)) => {
+                            if let Ok(mut queue) = queue.lock() {
+                                queue.push_back(revalidate);
+                            }
+                            Message::Listed(idx)
+                        }
+                        Ok(None) => Message::Done(idx, Ok(())),
+                        Err(err) => Message::Done(idx, Err(err)),
+                    };
+                    if tx.send(message).is_err() {
+                        return;
+                    }
                 }
             });
         }
+        drop(tx);
 
-        return Ok(res);
+        // Items of entries after the one currently passed on
+        let mut pending = vec![Vec::new(); entry_count];
+        let mut done = vec![false; entry_count];
+        let mut next = 0;
+        for message in rx {
+            let idx = match message {
+                // Items of listed entries found while revalidating are passed on as they come
+                Message::Item(idx, item) if config.sort || idx <= next => {
+                    if !forward(item) {
+                        return Ok(false);
+                    }
+                    continue;
+                }
+                Message::Item(idx, item) => {
+                    pending[idx].push(item);
+                    continue;
+                }
+                Message::Listed(idx) => idx,
+                Message::Done(idx, res) => {
+                    res?;
+                    idx
+                }
+            };
+            done[idx] = true;
+            while next < entry_count && done[next] {
+                next += 1;
+                for item in pending.get_mut(next).map(std::mem::take).unwrap_or_default() {
+                    if !forward(item) {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    })?;
+    if !completed {
+        return Ok(());
+    }
+
+    let mut orphans = Vec::new();
+    for (name, stats) in sessions.value() {
+        if !seen.contains(&name) {
+            stats.into_prompt_items(name, config, |item| orphans.push(item))?;
+        }
+    }
+    if config.sort {
+        orphans.sort_by(compare);
+    }
+
+    for item in orphans {
+        if !send(item) {
+            break;
+        }
+    }
+
+    return Ok(());
+}
+
+/// Attached session first, then running sessions with most windows first, then everything else by name.
+#[rustfmt::skip]
+fn compare(a: &PromptItem, b: &PromptItem) -> Ordering {
+    match (a, b) {
+        (PromptItem { stats: Some(SessionStats { attached: true, ..}), .. }, _) => Ordering::Less,
+        (_, PromptItem { stats: Some(SessionStats { attached: true, ..}), .. }) => Ordering::Greater,
+        (PromptItem { stats: Some(_), .. }, PromptItem { stats: None, .. }) => Ordering::Less,
+        (PromptItem { stats: None, .. }, PromptItem { stats: Some(_), .. }) => Ordering::Greater,
+        (PromptItem { stats: Some(SessionStats { window_count: c1, .. }), .. }, PromptItem { stats: Some(SessionStats { window_count: c2, .. }), .. }) if (c1 == c2) => Ordering::Equal,
+        (PromptItem { stats: Some(SessionStats { window_count: c1, .. }), .. }, PromptItem { stats: Some(SessionStats { window_count: c2, .. }), .. }) if (c1 > c2) => Ordering::Less,
+        (PromptItem { stats: Some(_), .. }, PromptItem { stats: Some(_), .. }) => Ordering::Greater,
+        (PromptItem { name: name1, .. }, PromptItem { name: name2, .. }) => name1.to_lowercase().cmp(&name2.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use super::{compare, find_prompt_item, stream_prompt_items, Placeholders, PromptItem};
+    use crate::config::{Config, Entry, EntryDir, EntryPlain, ScanOptions};
+    use crate::template::{Escape, Template};
+    use crate::tmux::{Executor, SessionStats, Sessions};
+
+    fn config(sort: bool) -> Config {
+        Config {
+            sort,
+            ..Default::default()
+        }
+    }
+
+    fn plain(name: &str) -> Entry {
+        Entry::Plain(EntryPlain {
+            name: name.to_owned(),
+            workdir: std::path::PathBuf::from("/").into(),
+            preview_cmd: None,
+            window: false,
+        })
+    }
+
+    fn sessions(names: &[(&str, u8, bool)]) -> Sessions {
+        let mut sessions = Sessions::default();
+        for (name, window_count, attached) in names {
+            sessions.value_ref_mut().insert(
+                name.to_string(),
+                SessionStats {
+                    window_count: *window_count,
+                    attached: *attached,
+                },
+            );
+        }
+        sessions
+    }
+
+    fn stream(sort: bool, sessions: Sessions, limit: usize) -> Vec<String> {
+        let entries = ["b", "C", "a", "running", "attached"].into_iter().map(plain).collect();
+        let mut res = Vec::new();
+        stream_prompt_items(entries, &config(sort), sessions, &Executor, |item| {
+            res.push(item.name);
+            res.len() < limit
+        })
+        .unwrap();
+        res
+    }
+
+    #[test]
+    fn streamed_items_keep_entry_order() {
+        assert_eq!(
+            stream(
+                false,
+                sessions(&[("running", 1, false), ("orphan", 3, false)]),
+                usize::MAX
+            ),
+            vec!["b", "C", "a", "running", "attached", "orphan"]
+        );
+    }
+
+    #[test]
+    fn sorted_items_are_streamed_as_found() {
+        let mut names = stream(
+            true,
+            sessions(&[("running", 1, false), ("attached", 1, true), ("orphan", 3, false)]),
+            usize::MAX,
+        );
+
+        assert_eq!(names.pop().as_deref(), Some("orphan"));
+        names.sort();
+        assert_eq!(names, vec!["C", "a", "attached", "b", "running"]);
+    }
+
+    #[test]
+    fn cached_dirs_are_streamed_before_revalidating() {
+        let (root, cache_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        fs::create_dir(root.path().join("a")).unwrap();
+        let entries = || {
+            vec![
+                Entry::Dir(EntryDir {
+                    name: "{{name}}".to_owned(),
+                    workdir: root.path().to_path_buf().into(),
+                    excludes: None,
+                    preview_cmd: None,
+                    scan: ScanOptions::default(),
+                    window: false,
+                }),
+                plain("z"),
+            ]
+        };
+        let mut config = config(false);
+        config.cache_dir = Some(cache_dir.path().to_path_buf());
+        let stream = || {
+            let mut res = Vec::new();
+            stream_prompt_items(entries(), &config, Sessions::default(), &Executor, |item| {
+                res.push(item.name);
+                true
+            })
+            .unwrap();
+            res
+        };
+
+        assert_eq!(stream(), vec!["a", "z"]);
+        fs::create_dir(root.path().join("b")).unwrap();
+        let mut warm = stream();
+
+        assert_eq!(warm.remove(0), "a");
+        warm.sort();
+        assert_eq!(warm, vec!["b", "z"]);
+    }
+
+    #[test]
+    fn streaming_stops_when_send_fails() {
+        assert_eq!(stream(false, Sessions::default(), 2), vec!["b", "C"]);
+    }
+
+    #[test]
+    fn listing_stops_when_for_each_returns_false() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        let entry = EntryDir {
+            name: "{{name}}".to_owned(),
+            workdir: dir.path().to_path_buf().into(),
+            excludes: None,
+            preview_cmd: None,
+            scan: ScanOptions::default(),
+            window: false,
+        };
+
+        let mut names = Vec::new();
+        entry
+            .into_prompt_items(&Sessions::default(), None, |item| {
+                names.push(item.name);
+                false
+            })
+            .unwrap();
+
+        assert_eq!(names, vec!["a"]);
+    }
+
+    #[test]
+    fn sort_key_orders_like_compare() {
+        let mut items = ["project-b", "Project-A", "zeta", "running", "attached", "busy", "alpha"]
+            .map(|name| PromptItem::new(name.to_owned(), std::path::PathBuf::from("/").into()));
+        let all_sessions = sessions(&[("running", 1, false), ("attached", 1, true), ("busy", 4, false)]);
+        for item in &mut items {
+            item.populate_session_data(&all_sessions);
+        }
+
+        let mut by_key = items.to_vec();
+        by_key.sort_by_key(PromptItem::sort_key);
+        let mut by_compare = items.to_vec();
+        by_compare.sort_by(compare);
+
+        let names = |items: Vec<PromptItem>| items.into_iter().map(|item| item.name).collect::<Vec<_>>();
+        let expected = vec!["attached", "busy", "running", "alpha", "Project-A", "project-b", "zeta"];
+        assert_eq!(names(by_compare), expected);
+        assert_eq!(names(by_key), expected);
+    }
+
+    #[test]
+    fn non_utf8_dirs_are_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let odd = OsStr::from_bytes(b"caf\xe9");
+        fs::create_dir(dir.path().join(odd)).unwrap();
+        fs::create_dir(dir.path().join("plain")).unwrap();
+        fs::create_dir(dir.path().join("skipped")).unwrap();
+        let entry = EntryDir {
+            name: "{{name}}".to_owned(),
+            workdir: dir.path().to_path_buf().into(),
+            excludes: Some(vec!["skip*".to_owned()]),
+            preview_cmd: None,
+            scan: ScanOptions::default(),
+            window: false,
+        };
+
+        let mut items = Vec::new();
+        entry
+            .into_prompt_items(&Sessions::default(), None, |item| {
+                items.push(item);
+                true
+            })
+            .unwrap();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "caf\u{fffd}");
+        assert_eq!(AsRef::<Path>::as_ref(&items[0].workdir), dir.path().join(odd));
+        assert_eq!(items[1].name, "plain");
+    }
+
+    #[test]
+    fn path_placeholders() {
+        let placeholders = Placeholders {
+            name: Some("api"),
+            workdir: Path::new("/home/test/code"),
+            dir: Path::new("/home/test/code/services/api"),
+            root: Some(Path::new("/home/test/code")),
+            package: None,
+        };
+        let template =
+            Template::parse("{{name}}|{{basename}}|{{parent}}|{{relpath}}|{{workdir}}|{{ relpath | replace:/:. }}")
+                .unwrap();
+
+        assert_eq!(
+            placeholders.render(&template, Escape::None).unwrap(),
+            "api|api|/home/test/code/services|services/api|/home/test/code|services.api"
+        );
+    }
+
+    #[test]
+    fn preview_values_are_quoted() {
+        let placeholders = Placeholders {
+            name: Some("it's"),
+            workdir: Path::new("/home/test/my dir"),
+            dir: Path::new("/home/test/my dir"),
+            root: None,
+            package: None,
+        };
+        let template = Template::parse("ls {{workdir}} {{relpath}} # {{name}}").unwrap();
+
+        assert_eq!(
+            placeholders.render(&template, Escape::Shell).unwrap(),
+            r"ls '/home/test/my dir' '/home/test/my dir' # 'it'\''s'"
+        );
+    }
+
+    #[test]
+    fn found_item_is_streamed_item() {
+        let entries = || ["a", "b"].into_iter().map(plain).collect::<Vec<_>>();
+        let config = config(false);
+
+        let orphan = find_prompt_item(
+            entries(),
+            &config,
+            sessions(&[("orphan", 2, false)]),
+            &Executor,
+            "orphan",
+        )
+        .unwrap();
+        let unknown = find_prompt_item(entries(), &config, Sessions::default(), &Executor, "unknown").unwrap();
+
+        assert_eq!(
+            orphan.and_then(|item| item.stats).map(|stats| stats.window_count),
+            Some(2)
+        );
+        assert_eq!(unknown, None);
+    }
+
+    #[test]
+    fn found_item_of_scanned_dir_entry_is_cached_dir() {
+        let (root, cache_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        fs::create_dir(root.path().join("a")).unwrap();
+        let entries = || {
+            vec![Entry::Dir(EntryDir {
+                name: "{{name}}".to_owned(),
+                workdir: root.path().to_path_buf().into(),
+                excludes: None,
+                preview_cmd: None,
+                scan: ScanOptions::default(),
+                window: false,
+            })]
+        };
+        let mut config = config(false);
+        config.cache_dir = Some(cache_dir.path().to_path_buf());
+        let find = |name| find_prompt_item(entries(), &config, Sessions::default(), &Executor, name).unwrap();
+
+        let scanned = find("a").unwrap();
+        fs::create_dir(root.path().join("b")).unwrap();
+        let cached = find("a").unwrap();
+        let not_rescanned = find("b");
+
+        assert_eq!(scanned.workdir.as_ref(), root.path().join("a"));
+        assert_eq!(cached.workdir.as_ref(), root.path().join("a"));
+        assert_eq!(not_rescanned, None);
     }
 }
@@ -0,0 +1,118 @@
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use log::debug;
+
+use crate::config::ScanOptions;
+use crate::error::{Context, Result};
+
+/// Files in the scanned root whose patterns are honoured when gitignore is enabled
+pub(crate) const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+/// Lists the subdirectories of root which are not filtered out by options, sorted by path.
+pub fn subdirs(root: &Path, options: &ScanOptions) -> Result<Vec<PathBuf>> {
+    let start = Instant::now();
+    let ignore = if options.gitignore {
+        Some(read_ignore_files(root)?)
+    } else {
+        None
+    };
+
+    let mut res = Vec::new();
+    for entry in fs::read_dir(root).scan_context(format!("Unable to read dir '{}'.", root.display()))? {
+        let entry = entry.scan_context("Unexpected error when reading dir.")?;
+        if options.skip_hidden && entry.file_name().as_bytes().starts_with(b".") {
+            continue;
+        }
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let is_dir = if file_type.is_symlink() {
+            options.follow_symlinks && entry.path().is_dir()
+        } else {
+            file_type.is_dir()
+        };
+        if !is_dir {
+            continue;
+        }
+
+        let path = entry.path();
+        if ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matched(&path, true).is_ignore())
+        {
+            continue;
+        }
+        res.push(path);
+    }
+    res.sort();
+
+    debug!(
+        "Scanned '{}' in {:.1?}, found {} dirs",
+        root.display(),
+        start.elapsed(),
+        res.len()
+    );
+    return Ok(res);
+}
+
+fn read_ignore_files(root: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for file in IGNORE_FILES {
+        let path = root.join(file);
+        if path.is_file() {
+            if let Some(err) = builder.add(&path) {
+                return Err(err).scan_context(format!("Unable to read '{}'", path.display()));
+            }
+        }
+    }
+    return builder.build().scan_context("Unable to build ignore rules");
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+
+    use test_case::test_case;
+
+    use super::subdirs;
+    use crate::config::ScanOptions;
+
+    fn names(root: &Path, options: ScanOptions) -> Vec<String> {
+        subdirs(root, &options)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [".hidden", "node_modules", "src", "target", "build-1", "build-2"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        fs::write(dir.path().join("file"), "").unwrap();
+        fs::write(dir.path().join(".gitignore"), "node_modules/\n/target\nbuild-*\n").unwrap();
+        fs::write(dir.path().join(".ignore"), "!build-2\n").unwrap();
+        symlink(dir.path().join("src"), dir.path().join("link")).unwrap();
+        symlink(dir.path().join("file"), dir.path().join("file-link")).unwrap();
+        dir
+    }
+
+    #[test_case(ScanOptions::default(), &[".hidden", "build-1", "build-2", "node_modules", "src", "target"] ; "defaults skip symlinks")]
+    #[test_case(ScanOptions { skip_hidden: true, ..Default::default() }, &["build-1", "build-2", "node_modules", "src", "target"] ; "skip hidden")]
+    #[test_case(ScanOptions { follow_symlinks: true, ..Default::default() }, &[".hidden", "build-1", "build-2", "link", "node_modules", "src", "target"] ; "follow symlinks")]
+    #[test_case(ScanOptions { gitignore: true, ..Default::default() }, &[".hidden", "build-2", "src"] ; "gitignore")]
+    fn scan(options: ScanOptions, expected: &[&str]) {
+        let dir = setup();
+
+        assert_eq!(names(dir.path(), options), expected);
+    }
+}
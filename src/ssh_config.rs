@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Context, Result};
+
+/// Reads all concrete hosts from an ssh config file, following `Include` directives.
+/// Host patterns containing wildcards or negations are skipped.
+pub fn read_hosts(path: &Path) -> Result<Vec<String>> {
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut hosts = Vec::new();
+    let mut visited = HashSet::new();
+    read_file(path, &base_dir, &mut hosts, &mut visited)?;
+
+    let mut seen = HashSet::new();
+    hosts.retain(|host| seen.insert(host.to_owned()));
+    return Ok(hosts);
+}
+
+fn read_file(path: &Path, base_dir: &Path, hosts: &mut Vec<String>, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    if !visited.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+    let content = fs::read_to_string(path).scan_context(format!("Unable to read ssh config '{}'", path.display()))?;
+    parse(&content, base_dir, hosts, visited)
+}
+
+fn parse(content: &str, base_dir: &Path, hosts: &mut Vec<String>, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    for line in content.lines() {
+        let Some((keyword, args)) = split_line(line) else {
+            continue;
+        };
+
+        if keyword.eq_ignore_ascii_case("host") {
+            hosts.extend(
+                args.into_iter()
+                    .filter(|host| !host.contains(['*', '?']) && !host.starts_with('!')),
+            );
+        } else if keyword.eq_ignore_ascii_case("include") {
+            for pattern in args {
+                let pattern = crate::utils::envsubst(&pattern)?;
+                let pattern = base_dir.join(pattern.as_ref());
+                let pattern = pattern.to_string_lossy();
+                let mut paths = glob::glob(&pattern)
+                    .parse_context(format!("Invalid Include pattern '{pattern}' in ssh config"))?
+                    .filter_map(Result::ok)
+                    .collect::<Vec<_>>();
+                paths.sort();
+                for path in paths {
+                    read_file(&path, base_dir, hosts, visited)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits a config line into its keyword and arguments. Keyword and arguments can be separated either by
+/// whitespace or by a single '='. Arguments may be quoted and everything after an unquoted '#' is a comment.
+fn split_line(line: &str) -> Option<(&str, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let keyword_end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(keyword_end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in rest.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes && current.is_empty() => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    return Some((keyword, args));
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+
+    use super::read_hosts;
+
+    #[test]
+    fn concrete_hosts() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        fs::write(
+            &config,
+            r#"
+# comment
+Host alpha
+    HostName alpha.example.com
+    User root
+
+Host beta gamma
+    Port 2222
+
+Host *
+    ServerAliveInterval 60
+
+Host *.internal !bastion dev-?
+    ProxyJump bastion
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(read_hosts(&config).unwrap(), vec!["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn keyword_case_equals_and_quotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        fs::write(&config, "HOST=alpha\nhost \"beta\"\n  Host   gamma  # trailing\n").unwrap();
+
+        assert_eq!(read_hosts(&config).unwrap(), vec!["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn includes_relative_and_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("config.d")).unwrap();
+        fs::write(dir.path().join("config.d").join("b.conf"), "Host from-b\n").unwrap();
+        fs::write(dir.path().join("config.d").join("a.conf"), "Host from-a\n").unwrap();
+        fs::write(dir.path().join("work"), "Host work\nInclude config\n").unwrap();
+        let config = dir.path().join("config");
+        fs::write(&config, "Include config.d/*.conf work\nHost main\nHost from-a\n").unwrap();
+
+        assert_eq!(read_hosts(&config).unwrap(), vec!["from-a", "from-b", "work", "main"]);
+    }
+
+    #[test]
+    fn missing_include_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        fs::write(&config, "Include does-not-exist\nHost alpha\n").unwrap();
+
+        assert_eq!(read_hosts(&config).unwrap(), vec!["alpha"]);
+    }
+
+    #[test]
+    fn missing_config_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(read_hosts(&dir.path().join("config")).is_err());
+    }
+}
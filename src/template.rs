@@ -0,0 +1,340 @@
+use std::borrow::Cow;
+
+use crate::error::{Error, Result};
+
+/// Placeholders available in every template. Besides these, environment variables can be used with `{{env.VAR}}`.
+pub const COMMON_PLACEHOLDERS: &[&str] = &[
+    "workdir",
+    "basename",
+    "parent",
+    "home_relative",
+    "git_branch",
+    "git_remote_repo",
+    "hostname",
+];
+
+/// Placeholders available in preview commands in addition to the common ones.
+pub const PREVIEW_PLACEHOLDERS: &[&str] = &["name", "relpath"];
+
+const ENV_PREFIX: &str = "env.";
+
+/// A string with `{{placeholder}}`s which are substituted when rendered. Placeholders can be followed by filters
+/// separated by '|', ie. `{{ name | replace:"-":"_" | truncate:20 | raw }}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder { name: String, filters: Vec<Filter> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// Do not escape the value
+    Raw,
+    Lower,
+    Replace {
+        from: String,
+        to: String,
+    },
+    /// Keep at most this many characters
+    Truncate(usize),
+}
+
+impl Filter {
+    fn apply<'a>(&self, value: Cow<'a, str>) -> Cow<'a, str> {
+        match self {
+            Filter::Raw => value,
+            Filter::Lower => Cow::Owned(value.to_lowercase()),
+            Filter::Replace { from, to } => Cow::Owned(value.replace(from.as_str(), to)),
+            Filter::Truncate(len) => match value.char_indices().nth(*len) {
+                Some((idx, _)) => Cow::Owned(value[..idx].to_owned()),
+                None => value,
+            },
+        }
+    }
+}
+
+/// How substituted values are escaped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escape {
+    /// Quote values so that a POSIX shell treats each of them as a single word
+    Shell,
+    /// Substitute values as they are
+    None,
+}
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_owned()));
+            }
+            let after_open = &rest[start + 2..];
+            let end = after_open
+                .find("}}")
+                .ok_or_else(|| Error::config(format!("Unclosed placeholder in template '{input}'")))?;
+            parts.push(parse_placeholder(&after_open[..end], input)?);
+            rest = &after_open[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_owned()));
+        }
+
+        return Ok(Self { parts });
+    }
+
+    /// Returns an error naming the first placeholder which is neither in known nor an environment variable.
+    pub fn validate(&self, known: &[&str]) -> Result<()> {
+        for name in self.placeholders() {
+            if !known.contains(&name) && !name.starts_with(ENV_PREFIX) {
+                return Err(Error::config(format!(
+                    "Unknown placeholder '{{{{{name}}}}}'. Available placeholders are: {}",
+                    known
+                        .iter()
+                        .map(|k| format!("{{{{{k}}}}}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder { name, .. } => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Renders the template, looking up placeholder values with lookup. Fails on placeholders without a value.
+    pub fn render<'a, F>(&self, escape: Escape, lookup: F) -> Result<String>
+    where
+        F: Fn(&str) -> Option<Cow<'a, str>>,
+    {
+        let mut res = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => res.push_str(literal),
+                Part::Placeholder { name, filters } => {
+                    let value = match name.strip_prefix(ENV_PREFIX) {
+                        Some(var) => Cow::Owned(std::env::var(var).unwrap_or_default()),
+                        None => lookup(name)
+                            .ok_or_else(|| Error::config(format!("Unknown placeholder '{{{{{name}}}}}'")))?,
+                    };
+                    let value = filters.iter().fold(value, |value, filter| filter.apply(value));
+                    let raw = filters.contains(&Filter::Raw);
+                    match escape {
+                        Escape::Shell if !raw => res.push_str(&shell_quote(&value)),
+                        _ => res.push_str(&value),
+                    }
+                }
+            }
+        }
+        return Ok(res);
+    }
+}
+
+fn parse_placeholder(content: &str, input: &str) -> Result<Part> {
+    let mut segments = split_unquoted(content, '|').into_iter();
+    let name = segments.next().unwrap_or_default();
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+        return Err(Error::config(format!(
+            "Invalid placeholder '{{{{{content}}}}}' in template '{input}'"
+        )));
+    }
+
+    let filters = segments
+        .map(|filter| parse_filter(filter.trim(), input))
+        .collect::<Result<Vec<_>>>()?;
+
+    return Ok(Part::Placeholder {
+        name: name.to_owned(),
+        filters,
+    });
+}
+
+fn parse_filter(filter: &str, input: &str) -> Result<Filter> {
+    let mut parts = split_unquoted(filter, ':').into_iter();
+    let name = parts.next().unwrap_or_default();
+    let args = parts.map(|arg| unquote(arg.trim())).collect::<Vec<_>>();
+
+    return match (name.trim(), args.as_slice()) {
+        ("raw", []) => Ok(Filter::Raw),
+        ("lower", []) => Ok(Filter::Lower),
+        ("replace", [from, to]) => Ok(Filter::Replace {
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+        ("truncate", [len]) => Ok(Filter::Truncate(len.parse().map_err(|_| {
+            Error::config(format!(
+                "Filter 'truncate' expects a number, got '{len}' in template '{input}'"
+            ))
+        })?)),
+        ("raw" | "lower" | "replace" | "truncate", _) => Err(Error::config(format!(
+            "Wrong number of arguments for filter '{filter}' in template '{input}'"
+        ))),
+        (name, _) => Err(Error::config(format!("Unknown filter '{name}' in template '{input}'"))),
+    };
+}
+
+/// Splits input on separator, ignoring separators inside double quotes.
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (idx, c) in input.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            res.push(&input[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+    res.push(&input[start..]);
+    return res;
+}
+
+fn unquote(input: &str) -> &str {
+    input
+        .strip_prefix('"')
+        .and_then(|i| i.strip_suffix('"'))
+        .unwrap_or(input)
+}
+
+/// Quotes value so that a POSIX shell treats it as a single word without expanding anything in it.
+pub fn shell_quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        return Cow::Borrowed(value);
+    }
+    return Cow::Owned(format!("'{}'", value.replace('\'', r"'\''")));
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{borrow::Cow, process::Command};
+
+    use test_case::test_case;
+
+    use super::{shell_quote, Escape, Template};
+
+    fn render(template: &str, escape: Escape, name: &str) -> String {
+        Template::parse(template)
+            .unwrap()
+            .render(escape, |key| match key {
+                "name" => Some(Cow::Borrowed(name)),
+                "workdir" => Some(Cow::Borrowed("/home/test")),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn substitutes_without_escaping() {
+        assert_eq!(
+            render("{{name}} in {{ workdir }}!", Escape::None, "it's mine"),
+            "it's mine in /home/test!"
+        );
+    }
+
+    #[test]
+    fn quotes_for_shell() {
+        assert_eq!(
+            render("ls {{workdir}}/{{name}}", Escape::Shell, "a b"),
+            "ls /home/test/'a b'"
+        );
+    }
+
+    #[test]
+    fn raw_filter_disables_quoting() {
+        assert_eq!(render("echo {{ name | raw }}", Escape::Shell, "$HOME"), "echo $HOME");
+    }
+
+    #[test_case("foo'; rm -rf ~ #" ; "quote and command")]
+    #[test_case("$(touch /tmp/pwned)" ; "command substitution")]
+    #[test_case("`id`" ; "backticks")]
+    #[test_case("a\nb" ; "newline")]
+    #[test_case("'\"\\" ; "quotes and backslash")]
+    #[test_case("" ; "empty")]
+    #[test_case("-n" ; "option")]
+    fn hostile_names_stay_a_single_word(name: &str) {
+        let cmd = render("printf '%s' {{name}}", Escape::Shell, name);
+
+        let output = Command::new("sh").arg("-c").arg(&cmd).output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), name);
+    }
+
+    #[test_case("safe-name_1.2", "safe-name_1.2" ; "safe")]
+    #[test_case("with space", "'with space'" ; "space")]
+    #[test_case("it's", r"'it'\''s'" ; "single quote")]
+    #[test_case("", "''" ; "empty")]
+    fn quote(input: &str, expected: &str) {
+        assert_eq!(shell_quote(input), expected);
+    }
+
+    #[test]
+    fn unknown_placeholder_fails_validation() {
+        let template = Template::parse("{{name}} {{nope}}").unwrap();
+
+        assert!(template.validate(&["name"]).is_err());
+        assert!(template.validate(&["name", "nope"]).is_ok());
+    }
+
+    #[test]
+    fn unknown_placeholder_fails_render() {
+        assert!(Template::parse("{{nope}}")
+            .unwrap()
+            .render(Escape::None, |_| None)
+            .is_err());
+    }
+
+    #[test_case("{{ name | lower }}", "mixed-case_name" ; "lower")]
+    #[test_case("{{ name | replace:\"-\":\"_\" }}", "Mixed_Case_Name" ; "replace")]
+    #[test_case("{{ name | replace:\"|\":\":\" }}", "Mixed-Case_Name" ; "replace with separators")]
+    #[test_case("{{ name | truncate:5 }}", "Mixed" ; "truncate")]
+    #[test_case("{{ name | truncate:50 }}", "Mixed-Case_Name" ; "truncate longer")]
+    #[test_case("{{ name | replace:_:. | lower | truncate:10 }}", "mixed-case" ; "chained")]
+    fn filters(template: &str, expected: &str) {
+        assert_eq!(render(template, Escape::None, "Mixed-Case_Name"), expected);
+    }
+
+    #[test]
+    fn env_placeholders() {
+        std::env::set_var("TMS_TEMPLATE_TEST", "from env");
+        let template = Template::parse("{{ env.TMS_TEMPLATE_TEST }} {{env.TMS_TEMPLATE_UNSET}}|").unwrap();
+
+        assert!(template.validate(&[]).is_ok());
+        assert_eq!(template.render(Escape::Shell, |_| None).unwrap(), "'from env' ''|");
+    }
+
+    #[test_case("{{name" ; "unclosed")]
+    #[test_case("{{}}" ; "empty")]
+    #[test_case("{{na me}}" ; "invalid name")]
+    #[test_case("{{name | nope}}" ; "unknown filter")]
+    #[test_case("{{name | truncate:x}}" ; "truncate without number")]
+    #[test_case("{{name | replace:a}}" ; "replace with one argument")]
+    #[test_case("{{name | lower:a}}" ; "lower with argument")]
+    fn invalid_templates(input: &str) {
+        assert!(Template::parse(input).is_err());
+    }
+
+    #[test]
+    fn tmux_formats_are_literals() {
+        assert_eq!(
+            render("tmux list-panes -F '#{pane_id}' -t {{name}}", Escape::Shell, "s"),
+            "tmux list-panes -F '#{pane_id}' -t s"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use log::debug;
+
+use crate::error::{Error, Result};
+
+use super::executor::{Execute, Executor, Output};
+use super::version::{Feature, Version};
+
+/// Commands which only query the server and can safely run in the context of the control mode client.
+const QUERIES: &[&str] = &[
+    "capture-pane",
+    "display-message",
+    "has-session",
+    "list-clients",
+    "list-panes",
+    "list-sessions",
+    "list-windows",
+    "show-options",
+];
+
+/// Executes tmux queries over a single control mode (`tmux -C`) connection instead of spawning tmux for each of
+/// them. Commands which change anything, chains containing them and other programs run through [`Executor`], as do
+/// all commands once the connection is lost.
+pub struct ControlExecutor {
+    socket: Option<PathBuf>,
+    connection: Mutex<Option<Connection>>,
+}
+
+struct Connection {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ControlExecutor {
+    /// Attaches a control mode client to the most recently used session of the server on socket, the default server
+    /// if `None`. Fails if the server is not running or tmux is older than 3.2, which added the client flags the
+    /// connection relies on.
+    pub fn connect(socket: Option<&Path>) -> Result<Self> {
+        Feature::ClientFlags.check(Version::detect(&Executor))?;
+
+        let mut cmd = Command::new("tmux");
+        if let Some(socket) = socket {
+            cmd.arg("-S").arg(socket);
+        }
+        let mut child = cmd
+            .args(["-C", "attach", "-f", "no-output,ignore-size"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| Error::spawn("tmux", err))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(Error::parse("Control mode client has no stdin or stdout"));
+        };
+
+        let mut connection = Connection {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        };
+        // Make sure the client attached before handing out the connection
+        connection
+            .send(&["display-message -p ''".to_owned()])?
+            .check("tmux", &["-C", "attach"])?;
+
+        return Ok(Self {
+            socket: socket.map(Path::to_path_buf),
+            connection: Mutex::new(Some(connection)),
+        });
+    }
+
+    /// Turns an invocation of tmux into lines for the control mode client, one per command. Returns `None` if it has
+    /// to run in its own process.
+    fn command_lines<S: AsRef<OsStr>>(&self, args: &[S]) -> Option<Vec<String>> {
+        let mut args = args
+            .iter()
+            .map(|arg| arg.as_ref().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        if let ["-S", socket, ..] = args[..] {
+            if self.socket.as_deref() != Some(Path::new(socket)) {
+                return None;
+            }
+            args.drain(..2);
+        }
+
+        return args
+            .split(|arg| *arg == ";")
+            .map(|command| match command.first() {
+                Some(name) if QUERIES.contains(name) && !command.iter().any(|arg| arg.contains('\n')) => Some(
+                    command
+                        .iter()
+                        .map(|arg| quote(&unescape(arg)))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+impl Execute for ControlExecutor {
+    fn execute<S: AsRef<OsStr>>(&self, program: &str, args: &[S]) -> Result<Output> {
+        let lines = match program {
+            "tmux" => self.command_lines(args),
+            _ => None,
+        };
+        let Some(lines) = lines else {
+            return Executor.execute(program, args);
+        };
+
+        let start = Instant::now();
+        let Ok(mut connection) = self.connection.lock() else {
+            return Executor.execute(program, args);
+        };
+        let Some(output) = connection.as_mut().and_then(|connection| connection.send(&lines).ok()) else {
+            // The server or the client went away, everything runs in its own process from now on
+            *connection = None;
+            return Executor.execute(program, args);
+        };
+        debug!(
+            "Ran '{}' in control mode in {:.1?}, {}",
+            lines.join(" ; "),
+            start.elapsed(),
+            output.status
+        );
+        return Ok(output);
+    }
+}
+
+impl Connection {
+    /// Sends the commands as one line and collects their replies. Commands after a failing one are not run, like when
+    /// they are chained on the command line.
+    fn send(&mut self, commands: &[String]) -> Result<Output> {
+        let closed = |_| Error::parse("Control mode connection was closed");
+        writeln!(self.stdin, "{}", commands.join(" ; ")).map_err(closed)?;
+        self.stdin.flush().map_err(closed)?;
+
+        let mut stdout = Vec::new();
+        for _ in commands {
+            let (success, body) = self.read_reply()?;
+            if !success {
+                return Ok(Output {
+                    stdout,
+                    stderr: body,
+                    status: ExitStatus::from_raw(1 << 8),
+                });
+            }
+            stdout.extend(body);
+        }
+        return Ok(Output {
+            stdout,
+            stderr: Vec::new(),
+            status: ExitStatus::from_raw(0),
+        });
+    }
+
+    /// Reads the next reply to a command sent by us, skipping notifications and the reply to the attach command. A
+    /// reply is framed by `%begin <time> <number> <flags>` and `%end` or `%error` with the same arguments.
+    fn read_reply(&mut self) -> Result<(bool, Vec<u8>)> {
+        loop {
+            let line = self.read_line()?;
+            let Some(guard) = line.strip_prefix(b"%begin ") else {
+                if line.starts_with(b"%exit") {
+                    return Err(Error::parse("Control mode client exited"));
+                }
+                continue;
+            };
+            let guard = guard.to_vec();
+
+            let mut body = Vec::new();
+            let success = loop {
+                let line = self.read_line()?;
+                if line.strip_prefix(b"%end ") == Some(&guard) {
+                    break true;
+                }
+                if line.strip_prefix(b"%error ") == Some(&guard) {
+                    break false;
+                }
+                body.extend_from_slice(&line);
+                body.push(b'\n');
+            };
+            // Replies to commands of the client have flags 1, the attach command itself has 0
+            if guard.ends_with(b" 1") {
+                return Ok((success, body));
+            }
+        }
+    }
+
+    fn read_line(&mut self) -> Result<Vec<u8>> {
+        let mut line = Vec::new();
+        match self.stdout.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => Err(Error::parse("Control mode connection was closed")),
+            Ok(_) => {
+                if line.ends_with(b"\n") {
+                    line.pop();
+                }
+                Ok(line)
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // An empty line detaches the client
+        let _ = writeln!(self.stdin);
+        let _ = self.child.wait();
+    }
+}
+
+/// Reverts the escaping of a trailing `;` done for tmux invoked with arguments, see [`super::escape`].
+fn unescape(arg: &str) -> String {
+    match arg.strip_suffix("\\;") {
+        Some(rest) => format!("{rest};"),
+        None => arg.to_owned(),
+    }
+}
+
+/// Quotes arg as a single word for the tmux command parser.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{quote, unescape};
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("it's #{x}"), r"'it'\''s #{x}'");
+        assert_eq!(quote(&unescape(r"make\;")), "'make;'");
+        assert_eq!(quote(&unescape(";")), "';'");
+    }
+}
@@ -1,16 +1,24 @@
+use std::ffi::OsStr;
+use std::os::unix::process::ExitStatusExt;
 use std::process::Stdio;
 use std::process::{Command, ExitStatus};
+use std::sync::Mutex;
+use std::time::Instant;
 
-use anyhow::Result;
+use log::debug;
+
+use crate::error::{Error, Result};
 
 pub trait Execute {
-    fn execute(&self, cmd: &str, args: &[&str], verbose: bool) -> Result<Output> {
-        let mut cmd = Command::new(cmd);
+    fn execute<S: AsRef<OsStr>>(&self, program: &str, args: &[S]) -> Result<Output> {
+        let mut cmd = Command::new(program);
         let cmd = cmd.args(args);
-        if verbose {
-            println!("Executing cmd: '{cmd:?}'");
-        }
-        let output = cmd.stdin(Stdio::inherit()).output()?;
+        let start = Instant::now();
+        let output = cmd
+            .stdin(Stdio::inherit())
+            .output()
+            .map_err(|err| Error::spawn(program, err))?;
+        debug!("Ran {cmd:?} in {:.1?}, {}", start.elapsed(), output.status);
 
         return Ok(Output {
             stdout: output.stdout,
@@ -22,6 +30,68 @@ pub trait Execute {
 pub struct Executor;
 impl Execute for Executor {}
 
+/// Test double which records every invocation instead of running it and answers with scripted outputs.
+#[derive(Default)]
+pub struct RecordingExecutor {
+    responses: Vec<(Vec<String>, i32, String)>,
+    calls: Mutex<Vec<Vec<String>>>,
+}
+
+impl RecordingExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers invocations starting with pattern, ie. `["tmux", "has-session"]`, with the exit code and stdout.
+    /// `"*"` matches any single argument. The first matching response wins, invocations matching none succeed with
+    /// empty output.
+    pub fn respond(mut self, pattern: &[&str], code: i32, stdout: &str) -> Self {
+        self.responses.push((
+            pattern.iter().map(|arg| arg.to_string()).collect(),
+            code,
+            stdout.to_owned(),
+        ));
+        self
+    }
+
+    /// Every invocation so far as the command followed by its arguments.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls.lock().map(|calls| calls.clone()).unwrap_or_default()
+    }
+
+    /// Like [`RecordingExecutor::calls`] with each invocation joined by spaces.
+    pub fn commands(&self) -> Vec<String> {
+        self.calls().iter().map(|call| call.join(" ")).collect()
+    }
+}
+
+impl Execute for RecordingExecutor {
+    fn execute<S: AsRef<OsStr>>(&self, cmd: &str, args: &[S]) -> Result<Output> {
+        let call = std::iter::once(cmd.to_owned())
+            .chain(args.iter().map(|arg| arg.as_ref().to_string_lossy().into_owned()))
+            .collect::<Vec<_>>();
+
+        let (code, stdout) = self
+            .responses
+            .iter()
+            .find(|(pattern, _, _)| {
+                pattern.len() <= call.len() && pattern.iter().zip(&call).all(|(p, arg)| p == "*" || p == arg)
+            })
+            .map_or((0, ""), |(_, code, stdout)| (*code, stdout.as_str()));
+
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(call);
+        }
+
+        return Ok(Output {
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+            status: ExitStatus::from_raw(code << 8),
+        });
+    }
+}
+
+#[derive(Debug)]
 pub struct Output {
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
@@ -29,12 +99,79 @@ pub struct Output {
 }
 
 impl Output {
-    pub fn print(&self) {
+    /// Turns an unsuccessful exit of the program invoked with args into [`Error::Command`].
+    pub fn check<S: AsRef<OsStr>>(self, program: &str, args: &[S]) -> Result<Self> {
+        if self.status.success() {
+            return Ok(self);
+        }
+        Err(Error::Command {
+            program: program.to_owned(),
+            args: args
+                .iter()
+                .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+                .collect(),
+            code: self.status.code(),
+            stderr: String::from_utf8_lossy(&self.stderr).trim().to_owned(),
+        })
+    }
+
+    /// Logs what the program printed, there is nothing to show the user after a successful run.
+    pub fn log(&self) {
         if !self.stdout.is_empty() {
-            println!("self.stdout = {}", String::from_utf8_lossy(&self.stdout));
+            debug!("stdout: {}", String::from_utf8_lossy(&self.stdout).trim_end());
         }
         if !self.stderr.is_empty() {
-            println!("self.stderr = {}", String::from_utf8_lossy(&self.stderr));
+            debug!("stderr: {}", String::from_utf8_lossy(&self.stderr).trim_end());
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{Execute, Executor, RecordingExecutor};
+    use crate::error::Error;
+
+    #[test]
+    fn scripted_responses() {
+        let executor = RecordingExecutor::new()
+            .respond(&["tmux", "*", "-t", "a"], 1, "")
+            .respond(&["tmux"], 0, "out");
+
+        let missing = executor.execute("tmux", &["has-session", "-t", "a"]).unwrap();
+        let other = executor.execute("tmux", &["has-session", "-t", "b"]).unwrap();
+        let unmatched = executor.execute("git", &["status"]).unwrap();
+
+        assert_eq!(missing.status.code(), Some(1));
+        assert_eq!((other.status.code(), other.stdout), (Some(0), b"out".to_vec()));
+        assert!(unmatched.status.success() && unmatched.stdout.is_empty());
+        assert_eq!(
+            executor.commands(),
+            vec!["tmux has-session -t a", "tmux has-session -t b", "git status"]
+        );
+    }
+
+    #[test]
+    fn missing_program_is_not_installed() {
+        let err = Executor
+            .execute("tms-surely-missing-program", &["--version"])
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::NotInstalled { ref program } if program == "tms-surely-missing-program"));
+        assert_eq!(err.exit_code(), 127);
+    }
+
+    #[test]
+    fn check_reports_failed_command() {
+        let executor = RecordingExecutor::new().respond(&["tmux"], 1, "");
+        let err = executor
+            .execute("tmux", &["kill-session", "-t", "a"])
+            .unwrap()
+            .check("tmux", &["kill-session", "-t", "a"])
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::Command { code: Some(1), ref args, .. } if args == &["kill-session", "-t", "a"]));
+    }
+}
@@ -1,59 +1,126 @@
+mod control;
 mod executor;
+mod version;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-use anyhow::Context;
-use anyhow::Result;
+use crate::error::{Context, Error, Result};
+use crate::plan::{Plan, Step};
 
+pub use self::control::ControlExecutor;
 pub use self::executor::Execute;
-use self::executor::Executor;
-use self::executor::Output;
+pub use self::executor::Executor;
+pub use self::executor::Output;
+pub use self::executor::RecordingExecutor;
+pub use self::version::{Feature, Version};
 
 pub struct Tmux<E: Execute> {
-    verbose: bool,
     executor: E,
+    /// Socket of the server to talk to, the default server if `None`
+    socket: Option<PathBuf>,
+    inside_tmux: bool,
+    /// Detected on first use, `None` inside if it is unknown
+    version: OnceLock<Option<Version>>,
 }
 
 impl Tmux<Executor> {
-    pub fn new(config: &crate::config::Config) -> Self {
-        Self {
-            executor: Executor,
-            verbose: config.verbose,
-        }
+    pub fn new() -> Self {
+        Self::new_with_executor(Executor)
     }
 }
 
 impl Default for Tmux<Executor> {
     fn default() -> Self {
-        Self {
-            executor: Executor,
-            verbose: false,
-        }
+        Self::new()
     }
 }
 
 impl<E: Execute> Tmux<E> {
-    pub fn new_with_executor(config: &crate::config::Config, executor: E) -> Self {
+    pub fn new_with_executor(executor: E) -> Self {
         Self {
             executor,
-            verbose: config.verbose,
+            socket: None,
+            inside_tmux: std::env::var("TMUX").is_ok(),
+            version: OnceLock::new(),
         }
     }
 
-    pub fn new_grouped_session(&self, session_name: &str) -> Result<Output> {
-        self.execute(&["new-session", "-t", session_name])
+    /// Talks to the server listening on socket instead of the default server.
+    pub fn with_socket(mut self, socket: impl Into<PathBuf>) -> Self {
+        self.socket = Some(socket.into());
+        self
+    }
+
+    /// Overrides whether tms runs inside a tmux client, which is detected from `$TMUX` by default.
+    pub fn with_inside_tmux(mut self, inside_tmux: bool) -> Self {
+        self.inside_tmux = inside_tmux;
+        self
+    }
+
+    pub fn is_inside_tmux(&self) -> bool {
+        self.inside_tmux
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    /// Version of the installed tmux, detected once with `tmux -V`. `None` if it is unknown, in which case every
+    /// feature is assumed to be supported.
+    pub fn version(&self) -> Option<Version> {
+        *self.version.get_or_init(|| Version::detect(&self.executor))
     }
 
-    pub fn new_session(&self, session_name: &str, cwd: &str, detached: bool) -> Result<Output> {
-        if detached {
-            self.execute(&["new-session", "-ds", session_name, "-c", cwd])
-        } else {
-            self.execute(&["new-session", "-s", session_name, "-c", cwd])
+    /// Whether the installed tmux supports feature.
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.check(self.version()).is_ok()
+    }
+
+    pub fn new_session(&self, session_name: &str, cwd: &Path, command: Option<&str>, detached: bool) -> Result<Output> {
+        let mut args = vec![
+            OsStr::new("new-session"),
+            OsStr::new(if detached { "-ds" } else { "-s" }),
+            OsStr::new(session_name),
+            OsStr::new("-c"),
+            cwd.as_os_str(),
+        ];
+        if let Some(command) = command {
+            args.push(OsStr::new(command));
+        }
+        self.execute(&args)
+    }
+
+    /// Performs the steps of plan in order, stopping at the first one tmux fails.
+    pub fn apply(&self, plan: &Plan) -> Result<()> {
+        if plan.steps().is_empty() {
+            return Ok(());
         }
+        let commands = plan.steps().iter().map(Step::args).collect::<Vec<_>>();
+        self.run_chain(&commands)?.log();
+        Ok(())
+    }
+
+    /// Runs commands in a single tmux invocation, like `tmux a \; b`. Commands after a failing one are not run.
+    pub fn run_chain<S: AsRef<OsStr>>(&self, commands: &[Vec<S>]) -> Result<Output> {
+        let args = chain(commands);
+        self.invoke(args.clone())?.check("tmux", &args)
+    }
+
+    /// Like [`Tmux::run_chain`] but leaves checking whether the commands succeeded to the caller.
+    fn execute_chain<S: AsRef<OsStr>>(&self, commands: &[Vec<S>]) -> Result<Output> {
+        self.invoke(chain(commands))
     }
 
     pub fn is_tmux_running(&self) -> Result<bool> {
-        Ok(self.executor.execute("pgrep", &["tmux"], false)?.status.success())
+        if self.socket.is_some() {
+            return Ok(self.list_sessions()?.status.success());
+        }
+        Ok(self.executor.execute("pgrep", &["tmux"])?.status.success())
     }
 
     pub fn has_session(&self, session_name: &str) -> Result<bool> {
@@ -72,36 +139,151 @@ impl<E: Execute> Tmux<E> {
         self.execute(&["list-sessions"])
     }
 
+    /// Captures content of the active pane in the session's current window, including escape sequences.
+    pub fn capture_pane(&self, session_name: &str) -> Result<Output> {
+        self.run(&["capture-pane", "-pe", "-t", &format!("={session_name}:")])
+    }
+
+    pub fn list_windows(&self, session_name: &str, format: &str) -> Result<Output> {
+        self.run(&["list-windows", "-t", &format!("={session_name}"), "-F", format])
+    }
+
+    /// Windows of the session tms runs in. The session is targeted explicitly, queries over a control mode connection
+    /// would list the windows of its own session otherwise.
+    pub fn current_windows(&self) -> Result<Vec<Window>> {
+        let target = current_target(
+            std::env::var("TMUX_PANE").ok().as_deref(),
+            std::env::var("TMUX").ok().as_deref(),
+        );
+        let mut args = vec!["list-windows", "-F", WINDOW_FORMAT];
+        if let Some(ref target) = target {
+            args.extend(["-t", target]);
+        }
+        Window::parse(&self.run(&args)?.stdout)
+    }
+
     pub fn kill_session(&self, session_name: &str) -> Result<Output> {
-        self.execute(&["kill-session", "-t", session_name])
+        self.run(&["kill-session", "-t", session_name])
+    }
+
+    /// Lists running sessions, or `None` if the server is not running. Sessions count as attached if a client other
+    /// than a control mode client, like the one of [`ControlExecutor`], shows them.
+    pub fn sessions(&self) -> Result<Option<Sessions>> {
+        let output = self.execute_chain(&[
+            vec!["list-sessions", "-F", SESSION_FORMAT],
+            vec!["list-clients", "-F", CLIENT_FORMAT],
+        ])?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(Sessions::parse(&output.stdout)?))
     }
 
     pub fn get_active_sessions(&self) -> Result<Sessions> {
-        String::from_utf8_lossy(&self.list_sessions()?.stdout)
-            .lines()
-            .try_fold(Sessions(HashMap::new()), |mut acc, input| -> Result<Sessions> {
-                let (name, rest) = input.split_once(": ").context("")?;
-                let (window_count, rest) = rest.split_once(' ').context("")?;
-                let active = rest.contains("attached");
-                acc.0.insert(
-                    name.to_owned(),
-                    SessionStats {
-                        window_count: window_count.parse()?,
-                        attached: active,
-                    },
-                );
-                Ok(acc)
-            })
-            .context("")
+        Ok(self.sessions()?.unwrap_or_default())
+    }
+
+    /// Like [`Tmux::execute`] but fails with [`Error::Command`] if tmux exits unsuccessfully.
+    fn run<S: AsRef<OsStr>>(&self, args: &[S]) -> Result<Output> {
+        self.execute(args)?.check("tmux", args)
     }
 
-    fn execute(&self, args: &[&str]) -> Result<Output> {
-        self.executor.execute("tmux", args, self.verbose)
+    fn execute<S: AsRef<OsStr>>(&self, args: &[S]) -> Result<Output> {
+        self.invoke(args.iter().map(|arg| escape(arg.as_ref())).collect())
+    }
+
+    /// Invokes tmux with args as they are, on the configured socket. Fails with [`Error::Unsupported`] instead of
+    /// running tmux if one of the commands needs a newer tmux.
+    fn invoke(&self, args: Vec<Cow<OsStr>>) -> Result<Output> {
+        for command in args.split(|arg| &**arg == OsStr::new(SEPARATOR)) {
+            if let Some(feature) = Feature::used_by(command) {
+                feature.check(self.version())?;
+            }
+        }
+        match self.socket {
+            Some(ref socket) => {
+                let mut all_args = vec![Cow::Borrowed(OsStr::new("-S")), Cow::Borrowed(socket.as_os_str())];
+                all_args.extend(args);
+                self.executor.execute("tmux", &all_args)
+            }
+            None => self.executor.execute("tmux", &args),
+        }
     }
 }
 
+/// Separates the commands of a single tmux invocation running several of them.
+const SEPARATOR: &str = ";";
+
+/// Format of the sessions [`Sessions::parse`] reads.
+const SESSION_FORMAT: &str = "session #{session_windows} #{session_name}";
+/// Format of the clients [`Sessions::parse`] reads, control mode clients print empty lines.
+const CLIENT_FORMAT: &str = "#{?client_control_mode,,client #{client_session}}";
+
+/// Format of the windows [`Window::parse`] reads.
+const WINDOW_FORMAT: &str = "#{window_id} #{pane_current_path}";
+
+/// Target of the session tms runs in, found the way tmux finds it for commands without a target: the pane from
+/// `$TMUX_PANE`, or else the session id at the end of `$TMUX`.
+fn current_target(tmux_pane: Option<&str>, tmux: Option<&str>) -> Option<String> {
+    if let Some(pane) = tmux_pane.filter(|pane| pane.starts_with('%')) {
+        return Some(pane.to_owned());
+    }
+    let id = tmux?.rsplit(',').next()?;
+    id.parse::<u32>().ok().map(|id| format!("${id}"))
+}
+
+/// Joins commands into the escaped arguments of a single tmux invocation.
+fn chain<S: AsRef<OsStr>>(commands: &[Vec<S>]) -> Vec<Cow<'_, OsStr>> {
+    let mut args = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        if i > 0 {
+            args.push(Cow::Borrowed(OsStr::new(SEPARATOR)));
+        }
+        args.extend(command.iter().map(|arg| escape(arg.as_ref())));
+    }
+    return args;
+}
+
+/// Escapes a trailing `;` of arg, which tmux would otherwise take as the end of the command.
+pub fn escape(arg: &OsStr) -> Cow<'_, OsStr> {
+    match arg.as_bytes().strip_suffix(SEPARATOR.as_bytes()) {
+        Some(rest) => Cow::Owned(OsString::from_vec([rest, b"\\;"].concat())),
+        None => Cow::Borrowed(arg),
+    }
+}
+
+#[derive(Default)]
 pub struct Sessions(HashMap<String, SessionStats>);
 impl Sessions {
+    /// Parses the sessions and clients listed by [`Tmux::sessions`].
+    pub fn parse(stdout: &[u8]) -> Result<Self> {
+        let mut sessions = HashMap::new();
+        for line in String::from_utf8_lossy(stdout).lines().filter(|line| !line.is_empty()) {
+            let invalid = || Error::parse(format!("Unexpected line '{line}' in the list of sessions"));
+            match line.split_once(' ').ok_or_else(invalid)? {
+                ("session", rest) => {
+                    let (window_count, name) = rest.split_once(' ').ok_or_else(invalid)?;
+                    sessions.insert(
+                        name.to_owned(),
+                        SessionStats {
+                            window_count: window_count
+                                .parse()
+                                .parse_context(format!("Invalid window count in '{line}'"))?,
+                            attached: false,
+                        },
+                    );
+                }
+                ("client", name) => {
+                    if let Some(stats) = sessions.get_mut(name) {
+                        stats.attached = true;
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Self(sessions))
+    }
+
     pub fn value(self) -> HashMap<String, SessionStats> {
         self.0
     }
@@ -118,3 +300,148 @@ pub struct SessionStats {
     pub window_count: u8,
     pub attached: bool,
 }
+
+/// Window of a session and the directory its active pane is in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window {
+    /// Unique id like `@3`, which is a target on its own
+    pub id: String,
+    pub workdir: PathBuf,
+}
+
+impl Window {
+    /// Parses the windows listed by [`Tmux::current_windows`].
+    pub fn parse(stdout: &[u8]) -> Result<Vec<Self>> {
+        String::from_utf8_lossy(stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (id, workdir) = line
+                    .split_once(' ')
+                    .ok_or_else(|| Error::parse(format!("Unexpected line '{line}' in the list of windows")))?;
+                Ok(Self {
+                    id: id.to_owned(),
+                    workdir: PathBuf::from(workdir),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::PathBuf;
+
+    use test_case::test_case;
+
+    use super::{current_target, Feature, RecordingExecutor, Tmux, Version, Window};
+    use crate::error::Error;
+
+    fn tmux() -> Tmux<RecordingExecutor> {
+        tmux_with(RecordingExecutor::new())
+    }
+
+    fn tmux_with(executor: RecordingExecutor) -> Tmux<RecordingExecutor> {
+        Tmux::new_with_executor(executor)
+    }
+
+    #[test]
+    fn chain_runs_commands_in_one_invocation() {
+        let tmux = tmux();
+
+        tmux.run_chain(&[
+            vec!["new-session", "-ds", "a;", "-c", "/", "make;"],
+            vec!["switch-client", "-t", "a;"],
+        ])
+        .unwrap();
+
+        assert_eq!(
+            tmux.executor().calls(),
+            vec![vec![
+                "tmux",
+                "new-session",
+                "-ds",
+                r"a\;",
+                "-c",
+                "/",
+                r"make\;",
+                ";",
+                "switch-client",
+                "-t",
+                r"a\;"
+            ]]
+        );
+    }
+
+    #[test]
+    fn single_commands_are_escaped() {
+        let tmux = tmux().with_socket("/tmp/tms.sock");
+
+        tmux.kill_session(";").unwrap();
+
+        assert_eq!(
+            tmux.executor().commands(),
+            vec![r"tmux -S /tmp/tms.sock kill-session -t \;"]
+        );
+    }
+
+    #[test]
+    fn commands_needing_newer_tmux_are_not_run() {
+        let tmux = tmux_with(RecordingExecutor::new().respond(&["tmux", "-V"], 0, "tmux 3.1c\n"));
+
+        let err = tmux
+            .run_chain(&[vec!["new-session", "-d", "-e", "A=1"], vec!["switch-client", "-t", "a"]])
+            .unwrap_err();
+        tmux.kill_session("a").unwrap();
+        assert!(tmux.run_chain(&[vec!["display-popup", "-E", "tms"]]).is_err());
+
+        assert!(matches!(
+            err,
+            Error::Unsupported {
+                feature: Feature::SessionEnvironment,
+                ..
+            }
+        ));
+        assert_eq!(tmux.version(), Some(Version::parse("tmux 3.1c").unwrap()));
+        assert!(!tmux.supports(Feature::Popup));
+        assert_eq!(tmux.executor().commands(), vec!["tmux -V", "tmux kill-session -t a"]);
+    }
+
+    #[test]
+    fn unknown_version_supports_everything() {
+        let tmux = tmux_with(RecordingExecutor::new().respond(&["tmux", "-V"], 0, "tmux master\n"));
+
+        tmux.run_chain(&[vec!["display-popup", "-E", "tms"]]).unwrap();
+
+        assert_eq!(tmux.version(), None);
+        assert_eq!(tmux.executor().commands(), vec!["tmux -V", "tmux display-popup -E tms"]);
+    }
+
+    #[test_case(Some("%4"), Some("/tmp/tmux-0/default,123,2"), Some("%4") ; "pane")]
+    #[test_case(None, Some("/tmp/tmux-0/default,123,2"), Some("$2") ; "session of popup")]
+    #[test_case(None, None, None ; "outside tmux")]
+    fn current_session_is_targeted_like_tmux_does(pane: Option<&str>, tmux: Option<&str>, expected: Option<&str>) {
+        assert_eq!(current_target(pane, tmux).as_deref(), expected);
+    }
+
+    #[test]
+    fn windows_with_workdir_of_active_pane() {
+        let windows = Window::parse(b"@1 /code/my proj\n@7 /\n").unwrap();
+
+        assert_eq!(
+            windows,
+            vec![
+                Window {
+                    id: "@1".to_owned(),
+                    workdir: PathBuf::from("/code/my proj"),
+                },
+                Window {
+                    id: "@7".to_owned(),
+                    workdir: PathBuf::from("/"),
+                },
+            ]
+        );
+        assert!(Window::parse(b"@1\n").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::ffi::OsStr;
+use std::fmt::Display;
+
+use crate::error::{Error, Result};
+
+use super::executor::Execute;
+
+/// Version of tmux as printed by `tmux -V`, ie. `tmux 3.3a` or `tmux next-3.4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    /// Letter of patch releases like `3.3a`
+    pub patch: Option<char>,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch: None,
+        }
+    }
+
+    /// Parses the output of `tmux -V`. Returns `None` for builds without a version number, like `tmux master` or
+    /// the OpenBSD base system tmux which carries the version of the OS.
+    pub fn parse(output: &str) -> Option<Self> {
+        let version = output.trim().strip_prefix("tmux ")?;
+        let version = version.strip_prefix("next-").unwrap_or(version);
+        let (major, rest) = version.split_once('.')?;
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (minor, suffix) = rest.split_at(digits);
+        return Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+            patch: suffix.chars().next().filter(char::is_ascii_lowercase),
+        });
+    }
+
+    /// Version of the tmux client, `None` if it is not installed or its version is unknown.
+    pub fn detect<E: Execute>(executor: &E) -> Option<Self> {
+        let output = executor.execute("tmux", &["-V"]).ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, "{patch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Commands and flags which are not available in every tmux version still in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// `new-session -e` to set environment variables of the session
+    SessionEnvironment,
+    /// `-f` of `list-sessions`, `list-windows` and `list-panes` to filter by a format
+    ListFilter,
+    /// `display-popup`
+    Popup,
+    /// `-f` of `attach-session` and `switch-client` to set client flags, which control mode relies on
+    ClientFlags,
+}
+
+impl Feature {
+    /// Oldest tmux version supporting the feature.
+    pub fn required(self) -> Version {
+        match self {
+            Self::SessionEnvironment | Self::ListFilter | Self::Popup | Self::ClientFlags => Version::new(3, 2),
+        }
+    }
+
+    /// Fails with [`Error::Unsupported`] if version is known to be too old for the feature.
+    pub fn check(self, version: Option<Version>) -> Result<()> {
+        match version {
+            Some(found) if found < self.required() => Err(Error::Unsupported {
+                feature: self,
+                required: self.required(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Feature a single tmux command relies on, if any.
+    pub fn used_by<S: AsRef<OsStr>>(command: &[S]) -> Option<Self> {
+        let (name, args) = command.split_first()?;
+        let flag = |flag: &str| args.iter().any(|arg| arg.as_ref() == flag);
+        match name.as_ref().to_str()? {
+            "new-session" | "new" if flag("-e") => Some(Self::SessionEnvironment),
+            "list-sessions" | "ls" | "list-windows" | "lsw" | "list-panes" | "lsp" if flag("-f") => {
+                Some(Self::ListFilter)
+            }
+            "display-popup" | "popup" => Some(Self::Popup),
+            "attach-session" | "attach" | "a" | "switch-client" | "switchc" if flag("-f") => Some(Self::ClientFlags),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::SessionEnvironment => "new-session -e",
+            Self::ListFilter => "list-sessions -f",
+            Self::Popup => "display-popup",
+            Self::ClientFlags => "attach-session -f",
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Feature, Version};
+    use crate::error::Error;
+
+    #[test_case("tmux 3.3a\n", Some("3.3a") ; "patch release")]
+    #[test_case("tmux 3.2", Some("3.2") ; "release")]
+    #[test_case("tmux next-3.5", Some("3.5") ; "development build")]
+    #[test_case("tmux 3.0-rc5", Some("3.0") ; "release candidate")]
+    #[test_case("tmux master", None ; "unversioned build")]
+    #[test_case("tmux openbsd-7.4", None ; "openbsd")]
+    fn parse(output: &str, expected: Option<&str>) {
+        assert_eq!(Version::parse(output).map(|v| v.to_string()).as_deref(), expected);
+    }
+
+    #[test]
+    fn patch_releases_are_newer() {
+        assert!(Version::parse("tmux 3.1c").unwrap() < Version::new(3, 2));
+        assert!(Version::parse("tmux 3.2a").unwrap() > Version::new(3, 2));
+        assert!(Version::parse("tmux 3.10").unwrap() > Version::new(3, 2));
+    }
+
+    #[test_case(&["new-session", "-d", "-e", "A=1"], Some(Feature::SessionEnvironment) ; "session environment")]
+    #[test_case(&["new-session", "-ds", "proj"], None ; "plain new session")]
+    #[test_case(&["list-sessions", "-F", "#{session_name}"], None ; "format is no filter")]
+    #[test_case(&["lsw", "-f", "#{window_active}"], Some(Feature::ListFilter) ; "alias with filter")]
+    #[test_case(&["display-popup", "-E", "tms"], Some(Feature::Popup) ; "popup")]
+    #[test_case(&["attach", "-f", "read-only"], Some(Feature::ClientFlags) ; "client flags")]
+    fn used_by(command: &[&str], expected: Option<Feature>) {
+        assert_eq!(Feature::used_by(command), expected);
+    }
+
+    #[test]
+    fn check_names_required_version() {
+        let err = Feature::Popup.check(Version::parse("tmux 3.1c")).unwrap_err();
+
+        assert!(matches!(err, Error::Unsupported { .. }));
+        assert_eq!(
+            err.to_string(),
+            "'display-popup' needs tmux 3.2 or newer, found tmux 3.1c"
+        );
+        assert!(Feature::Popup.check(Version::parse("tmux 3.2")).is_ok());
+        assert!(Feature::Popup.check(None).is_ok());
+    }
+}
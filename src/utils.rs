@@ -1,29 +1,220 @@
-use std::{env, sync::Arc};
+use std::{env, fs, sync::Arc};
 
-use anyhow::{Context, Result};
+use crate::error::{Context, Error, Result};
 
+/// Expands environment variables and a leading tilde in input the way a POSIX shell would.
+///
+/// Supported forms are `$VAR`, `${VAR}`, `${VAR:-default}` (default used when VAR is unset or empty),
+/// `${VAR-default}` (default used when VAR is unset), `~` and `~user`. Defaults are expanded as well. A backslash
+/// escapes the following `$`, `~` or `\`.
 pub fn envsubst(input: &str) -> Result<Arc<str>> {
     Ok(Arc::from(
-        input
-            .split('/')
-            .map(|word| {
-                if let Some(s) = word.strip_prefix('$') {
-                    return env::var(s).context(format!("Failed to substitute env variable '{s}'"));
-                } else if word == "~" {
-                    return env::var("HOME").context("Failed to substitute HOME for tilde");
-                };
-                return Ok(word.to_string());
-            })
-            .collect::<Result<Vec<String>>>()?
-            .join("/"),
+        expand(input, |var| env::var(var).ok(), user_home).config_context(format!("Failed to expand '{input}'"))?,
     ))
 }
 
-pub fn is_dir(entry: &Result<std::fs::DirEntry, std::io::Error>) -> bool {
-    if let Ok(entry) = entry {
-        if let Ok(metadata) = entry.metadata() {
-            return metadata.is_dir();
+fn expand<V, H>(input: &str, var: V, user_home: H) -> Result<String>
+where
+    V: Fn(&str) -> Option<String> + Copy,
+    H: Fn(&str) -> Option<String> + Copy,
+{
+    let mut res = String::with_capacity(input.len());
+    let mut rest = input;
+
+    if let Some(after_tilde) = rest.strip_prefix('~') {
+        let user_end = after_tilde.find('/').unwrap_or(after_tilde.len());
+        let (user, after_user) = after_tilde.split_at(user_end);
+        let home = if user.is_empty() {
+            var("HOME").ok_or_else(|| Error::config("Cannot expand '~', environment variable 'HOME' is not set"))?
+        } else {
+            user_home(user)
+                .ok_or_else(|| Error::config(format!("Cannot expand '~{user}', user '{user}' was not found")))?
+        };
+        res.push_str(&home);
+        rest = after_user;
+    }
+
+    while let Some(idx) = rest.find(['$', '\\']) {
+        res.push_str(&rest[..idx]);
+        let special = &rest[idx..];
+
+        if let Some(escaped) = special.strip_prefix('\\') {
+            match escaped.chars().next() {
+                Some(c @ ('$' | '~' | '\\')) => {
+                    res.push(c);
+                    rest = &escaped[1..];
+                }
+                _ => {
+                    res.push('\\');
+                    rest = escaped;
+                }
+            }
+            continue;
+        }
+
+        let after_dollar = &special[1..];
+        if let Some(braced) = after_dollar.strip_prefix('{') {
+            let end =
+                closing_brace(braced).ok_or_else(|| Error::config(format!("Missing closing '}}' in '{special}'")))?;
+            res.push_str(&expand_braced(&braced[..end], var, user_home)?);
+            rest = &braced[end + 1..];
+        } else {
+            let name_end = after_dollar
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after_dollar.len());
+            if name_end == 0 {
+                res.push('$');
+            } else {
+                let name = &after_dollar[..name_end];
+                res.push_str(
+                    &var(name).ok_or_else(|| Error::config(format!("Environment variable '{name}' is not set")))?,
+                );
+            }
+            rest = &after_dollar[name_end..];
         }
     }
-    return false;
+    res.push_str(rest);
+
+    return Ok(res);
+}
+
+/// Expands content of `${...}`.
+fn expand_braced<V, H>(content: &str, var: V, user_home: H) -> Result<String>
+where
+    V: Fn(&str) -> Option<String> + Copy,
+    H: Fn(&str) -> Option<String> + Copy,
+{
+    let name_end = content
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(content.len());
+    let (name, operator) = content.split_at(name_end);
+    if name.is_empty() {
+        return Err(Error::config(format!("Invalid variable name in '${{{content}}}'")));
+    }
+    let value = var(name);
+
+    return match (operator, value) {
+        ("", Some(value)) => Ok(value),
+        ("", None) => Err(Error::config(format!("Environment variable '{name}' is not set"))),
+        (operator, value) if operator.starts_with(":-") => match value.filter(|v| !v.is_empty()) {
+            Some(value) => Ok(value),
+            None => expand(&operator[2..], var, user_home),
+        },
+        (operator, value) if operator.starts_with('-') => match value {
+            Some(value) => Ok(value),
+            None => expand(&operator[1..], var, user_home),
+        },
+        (operator, _) => Err(Error::config(format!(
+            "Unsupported operator '{operator}' in '${{{content}}}'"
+        ))),
+    };
+}
+
+/// Finds the '}' closing an already opened '${', skipping nested '${...}'.
+fn closing_brace(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '$' if chars.peek().is_some_and(|(_, c)| *c == '{') => {
+                chars.next();
+                depth += 1;
+            }
+            '}' if depth == 0 => return Some(idx),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    return None;
+}
+
+/// Looks up home directory of user with getpwnam_r, so users from NSS sources like LDAP are found as well.
+fn user_home(user: &str) -> Option<String> {
+    let user = nix::unistd::User::from_name(user).ok()??;
+    return Some(user.dir.to_string_lossy().into_owned());
+}
+
+pub fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|hostname| hostname.trim().to_owned())
+        .or_else(|_| env::var("HOSTNAME"))
+        .unwrap_or_default()
+}
+
+/// Replaces the home directory prefix of path with '~'.
+pub fn home_relative(path: &std::path::Path) -> String {
+    match env::var("HOME")
+        .ok()
+        .and_then(|home| path.strip_prefix(home).ok().map(|p| p.to_owned()))
+    {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_owned(),
+        Some(rest) => format!("~/{}", rest.to_string_lossy()),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use test_case::test_case;
+
+    use super::expand;
+
+    fn var(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/test".to_owned()),
+            "XDG_DATA_HOME" => Some("/data".to_owned()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn user_home(user: &str) -> Option<String> {
+        (user == "alice").then(|| "/home/alice".to_owned())
+    }
+
+    #[test_case("/plain/path", "/plain/path" ; "no expansion")]
+    #[test_case("~", "/home/test" ; "tilde")]
+    #[test_case("~/code", "/home/test/code" ; "tilde slash")]
+    #[test_case("~alice/src", "/home/alice/src" ; "tilde user")]
+    #[test_case("/a/~/b", "/a/~/b" ; "tilde not at start")]
+    #[test_case("$HOME/code", "/home/test/code" ; "bare")]
+    #[test_case("${HOME}/code", "/home/test/code" ; "braced")]
+    #[test_case("$HOME-backup", "/home/test-backup" ; "bare followed by dash")]
+    #[test_case("${HOME}backup", "/home/testbackup" ; "braced followed by text")]
+    #[test_case("$XDG_DATA_HOME/x", "/data/x" ; "set with default unused")]
+    #[test_case("${UNSET:-/fallback}/x", "/fallback/x" ; "default")]
+    #[test_case("${EMPTY:-/fallback}", "/fallback" ; "default for empty")]
+    #[test_case("${EMPTY-/fallback}", "" ; "dash default keeps empty")]
+    #[test_case("${UNSET:-$HOME/.local/share}/x", "/home/test/.local/share/x" ; "nested default")]
+    #[test_case("${UNSET:-${XDG_DATA_HOME}}/x", "/data/x" ; "nested braced default")]
+    #[test_case("\\$HOME/\\~", "$HOME/~" ; "escaped")]
+    #[test_case("\\~/x", "~/x" ; "escaped tilde")]
+    #[test_case("C:\\dir", "C:\\dir" ; "other backslash kept")]
+    #[test_case("cost$ and $/", "cost$ and $/" ; "lone dollar")]
+    fn expands(input: &str, expected: &str) {
+        assert_eq!(expand(input, var, user_home).unwrap(), expected);
+    }
+
+    #[test_case("$UNSET/x", "'UNSET'" ; "unset bare")]
+    #[test_case("${UNSET}", "'UNSET'" ; "unset braced")]
+    #[test_case("${HOME", "closing" ; "unterminated")]
+    #[test_case("${}", "Invalid variable name" ; "empty name")]
+    #[test_case("${HOME:=x}", "Unsupported operator" ; "unsupported operator")]
+    #[test_case("~bob/x", "user 'bob'" ; "unknown user")]
+    fn errors(input: &str, message: &str) {
+        let err = expand(input, var, user_home).unwrap_err().to_string();
+
+        assert!(err.contains(message), "'{err}' does not contain '{message}'");
+    }
+
+    #[test]
+    fn looks_up_home_of_user() {
+        assert!(super::user_home("root").is_some());
+        assert_eq!(super::user_home("no-such-user-for-tms"), None);
+    }
 }
@@ -0,0 +1,393 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{Context, Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub path: PathBuf,
+    pub package: Option<String>,
+}
+
+/// Reads workspace members declared in any of the supported manifests found in root. Supported manifests are
+/// `Cargo.toml` (`[workspace].members`), `pnpm-workspace.yaml`, `package.json` (`workspaces`) and `go.work`.
+pub fn read_members(root: &Path) -> Result<Vec<Member>> {
+    let mut found_manifest = false;
+    let mut members = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (manifest, read_patterns) in MANIFESTS {
+        let path = root.join(manifest);
+        if !path.is_file() {
+            continue;
+        }
+        let Some(patterns) = read_patterns(&path)? else {
+            continue;
+        };
+        found_manifest = true;
+
+        for path in expand(root, &patterns)? {
+            if seen.insert(path.clone()) {
+                members.push(Member {
+                    package: package_name(&path),
+                    path,
+                });
+            }
+        }
+    }
+
+    if !found_manifest {
+        return Err(Error::scan(format!(
+            "No workspace manifest found in '{}'",
+            root.display()
+        )));
+    }
+    return Ok(members);
+}
+
+type ReadPatterns = fn(&Path) -> Result<Option<Patterns>>;
+
+const MANIFESTS: [(&str, ReadPatterns); 4] = [
+    ("Cargo.toml", cargo_patterns),
+    ("pnpm-workspace.yaml", pnpm_patterns),
+    ("package.json", npm_patterns),
+    ("go.work", go_patterns),
+];
+
+#[derive(Debug, Default, PartialEq)]
+struct Patterns {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Patterns {
+    /// Splits patterns into includes and excludes, where excludes are prefixed by '!'.
+    fn from_negatable(patterns: impl IntoIterator<Item = String>) -> Self {
+        patterns.into_iter().fold(Patterns::default(), |mut acc, pattern| {
+            match pattern.strip_prefix('!') {
+                Some(exclude) => acc.exclude.push(exclude.to_owned()),
+                None => acc.include.push(pattern),
+            }
+            acc
+        })
+    }
+}
+
+fn expand(root: &Path, patterns: &Patterns) -> Result<Vec<PathBuf>> {
+    let excludes = patterns
+        .exclude
+        .iter()
+        .map(|pattern| glob::Pattern::new(&root.join(normalize(pattern)).to_string_lossy()))
+        .collect::<Result<Vec<_>, _>>()
+        .parse_context("Invalid exclude pattern in workspace manifest")?;
+
+    let mut members = Vec::new();
+    for pattern in &patterns.include {
+        let full_pattern = root.join(normalize(pattern));
+        let mut paths = glob::glob(&full_pattern.to_string_lossy())
+            .parse_context(format!("Invalid member pattern '{pattern}' in workspace manifest"))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_dir())
+            .filter(|path| !excludes.iter().any(|exclude| exclude.matches_path(path)))
+            .collect::<Vec<_>>();
+        paths.sort();
+        members.extend(paths);
+    }
+    return Ok(members);
+}
+
+fn normalize(pattern: &str) -> &str {
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    pattern.strip_suffix('/').unwrap_or(pattern)
+}
+
+fn cargo_patterns(path: &Path) -> Result<Option<Patterns>> {
+    #[derive(Deserialize)]
+    struct Manifest {
+        workspace: Option<Workspace>,
+    }
+    #[derive(Deserialize)]
+    struct Workspace {
+        #[serde(default)]
+        members: Vec<String>,
+        #[serde(default)]
+        exclude: Vec<String>,
+    }
+
+    let manifest: Manifest =
+        toml::from_str(&read(path)?).parse_context(format!("Unable to parse '{}'", path.display()))?;
+    return Ok(manifest.workspace.map(|workspace| Patterns {
+        include: workspace.members,
+        exclude: workspace.exclude,
+    }));
+}
+
+fn npm_patterns(path: &Path) -> Result<Option<Patterns>> {
+    #[derive(Deserialize)]
+    struct Manifest {
+        workspaces: Option<Workspaces>,
+    }
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Workspaces {
+        List(Vec<String>),
+        Object {
+            #[serde(default)]
+            packages: Vec<String>,
+        },
+    }
+
+    let manifest: Manifest =
+        serde_json::from_str(&read(path)?).parse_context(format!("Unable to parse '{}'", path.display()))?;
+    return Ok(manifest.workspaces.map(|workspaces| match workspaces {
+        Workspaces::List(packages) | Workspaces::Object { packages } => Patterns::from_negatable(packages),
+    }));
+}
+
+/// Reads the `packages` list from pnpm-workspace.yaml. Only the block list form used by pnpm is supported.
+fn pnpm_patterns(path: &Path) -> Result<Option<Patterns>> {
+    let content = read(path)?;
+    let mut in_packages = false;
+    let mut packages = Vec::new();
+
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or_default().trim_end();
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = line.trim() == "packages:";
+            continue;
+        }
+        if let Some(item) = line.trim().strip_prefix('-').filter(|_| in_packages) {
+            packages.push(item.trim().trim_matches(['\'', '"']).to_owned());
+        }
+    }
+
+    return Ok(Some(Patterns::from_negatable(packages)));
+}
+
+fn go_patterns(path: &Path) -> Result<Option<Patterns>> {
+    let content = read(path)?;
+    let mut in_block = false;
+    let mut include = Vec::new();
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                include.push(line.trim_matches('"').to_owned());
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() {
+                include.push(rest.trim_matches('"').to_owned());
+            }
+        }
+    }
+
+    return Ok(Some(Patterns {
+        include,
+        exclude: Vec::new(),
+    }));
+}
+
+/// Reads the package name of a member from its own manifest.
+fn package_name(member: &Path) -> Option<String> {
+    #[derive(Deserialize)]
+    struct CargoManifest {
+        package: Option<CargoPackage>,
+    }
+    #[derive(Deserialize)]
+    struct CargoPackage {
+        name: String,
+    }
+    #[derive(Deserialize)]
+    struct NpmManifest {
+        name: Option<String>,
+    }
+
+    if let Ok(content) = fs::read_to_string(member.join("Cargo.toml")) {
+        if let Some(package) = toml::from_str::<CargoManifest>(&content).ok().and_then(|m| m.package) {
+            return Some(package.name);
+        }
+    }
+    if let Ok(content) = fs::read_to_string(member.join("package.json")) {
+        if let Some(name) = serde_json::from_str::<NpmManifest>(&content).ok().and_then(|m| m.name) {
+            return Some(name);
+        }
+    }
+    if let Ok(content) = fs::read_to_string(member.join("go.mod")) {
+        return content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| module.trim().trim_matches('"').to_owned());
+    }
+    return None;
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).scan_context(format!("Unable to read '{}'", path.display()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{read_members, Member};
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn members(root: &Path) -> Vec<(String, Option<String>)> {
+        read_members(root)
+            .unwrap()
+            .into_iter()
+            .map(|Member { path, package }| (path.strip_prefix(root).unwrap().to_string_lossy().into_owned(), package))
+            .collect()
+    }
+
+    #[test]
+    fn cargo_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/ignored\"]\n",
+        );
+        write(root, "crates/core/Cargo.toml", "[package]\nname = \"my-core\"\n");
+        write(root, "crates/macros/Cargo.toml", "[package]\nname = \"my-macros\"\n");
+        write(root, "crates/ignored/Cargo.toml", "[package]\nname = \"ignored\"\n");
+        write(root, "crates/README.md", "not a member");
+        write(root, "tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+
+        assert_eq!(
+            members(root),
+            vec![
+                ("crates/core".to_owned(), Some("my-core".to_owned())),
+                ("crates/macros".to_owned(), Some("my-macros".to_owned())),
+                ("tools/cli".to_owned(), Some("cli".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn cargo_without_workspace_is_not_a_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "Cargo.toml", "[package]\nname = \"single\"\n");
+
+        assert!(read_members(dir.path()).is_err());
+    }
+
+    #[test]
+    fn pnpm_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "# comment\npackages:\n  - 'packages/*'\n  - \"apps/web\" # the app\n  - '!packages/private'\ncatalog:\n  - nope\n",
+        );
+        write(root, "packages/ui/package.json", r#"{ "name": "@acme/ui" }"#);
+        write(root, "packages/private/package.json", r#"{ "name": "@acme/private" }"#);
+        write(root, "apps/web/package.json", r#"{ "name": "web" }"#);
+        write(root, "nope/package.json", r#"{ "name": "nope" }"#);
+
+        assert_eq!(
+            members(root),
+            vec![
+                ("packages/ui".to_owned(), Some("@acme/ui".to_owned())),
+                ("apps/web".to_owned(), Some("web".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn npm_workspaces_list_and_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{ "name": "root", "workspaces": ["packages/*"] }"#,
+        );
+        write(root, "packages/a/package.json", r#"{ "name": "a" }"#);
+        write(root, "packages/b/package.json", r#"{ "version": "1.0.0" }"#);
+
+        assert_eq!(
+            members(root),
+            vec![
+                ("packages/a".to_owned(), Some("a".to_owned())),
+                ("packages/b".to_owned(), None)
+            ]
+        );
+
+        write(
+            root,
+            "package.json",
+            r#"{ "workspaces": { "packages": ["packages/a"] } }"#,
+        );
+
+        assert_eq!(members(root), vec![("packages/a".to_owned(), Some("a".to_owned()))]);
+    }
+
+    #[test]
+    fn go_work() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "go.work",
+            "go 1.21\n\nuse ./tools\n\nuse (\n\t./svc/api // api\n\t./svc/worker\n)\n",
+        );
+        write(root, "tools/go.mod", "module example.com/tools\n");
+        write(root, "svc/api/go.mod", "module example.com/svc/api\n\ngo 1.21\n");
+        write(root, "svc/worker/go.mod", "module \"example.com/svc/worker\"\n");
+
+        assert_eq!(
+            members(root),
+            vec![
+                ("tools".to_owned(), Some("example.com/tools".to_owned())),
+                ("svc/api".to_owned(), Some("example.com/svc/api".to_owned())),
+                ("svc/worker".to_owned(), Some("example.com/svc/worker".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_manifests_are_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(root, "package.json", r#"{ "workspaces": ["js/*", "crates/wasm"] }"#);
+        write(root, "crates/wasm/Cargo.toml", "[package]\nname = \"wasm\"\n");
+        write(root, "js/app/package.json", r#"{ "name": "app" }"#);
+
+        assert_eq!(
+            members(root),
+            vec![
+                ("crates/wasm".to_owned(), Some("wasm".to_owned())),
+                ("js/app".to_owned(), Some("app".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(read_members(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use crate::config::EntryZoxide;
+use crate::error::{Context, Result};
+use crate::tmux::Execute;
+
+/// Returns directories for a zoxide entry ordered by score, highest first.
+pub fn read_dirs<E: Execute>(entry: &EntryZoxide, executor: &E) -> Result<Vec<PathBuf>> {
+    let content = match entry.source {
+        Some(ref source) => {
+            fs::read(source).scan_context(format!("Unable to read directory list '{}'", source.display()))?
+        }
+        None => {
+            let args = ["query", "-ls"];
+            executor.execute("zoxide", &args)?.check("zoxide", &args)?.stdout
+        }
+    };
+
+    return Ok(select(parse(&content), entry.roots.as_deref(), entry.limit));
+}
+
+/// Parses lines in the format of `zoxide query -ls`, ie. `<score> <path>`. Lines without a score are accepted as
+/// well and get a score of zero. Paths are taken as they are, they do not need to be UTF-8.
+fn parse(content: &[u8]) -> Vec<(f64, PathBuf)> {
+    let path = |bytes: &[u8]| PathBuf::from(OsStr::from_bytes(bytes));
+    content
+        .split(|&byte| byte == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let Some(end) = line.iter().position(u8::is_ascii_whitespace) else {
+                return (0.0, path(line));
+            };
+            match std::str::from_utf8(&line[..end]).map(str::parse::<f64>) {
+                Ok(Ok(score)) => (score, path(line[end..].trim_ascii_start())),
+                _ => (0.0, path(line)),
+            }
+        })
+        .collect()
+}
+
+fn select(mut dirs: Vec<(f64, PathBuf)>, roots: Option<&[PathBuf]>, limit: Option<usize>) -> Vec<PathBuf> {
+    if let Some(roots) = roots {
+        dirs.retain(|(_, path)| roots.iter().any(|root| path.starts_with(root)));
+    }
+    dirs.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+    return dirs
+        .into_iter()
+        .map(|(_, path)| path)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    use super::{parse, select};
+
+    #[test]
+    fn parse_zoxide_output() {
+        let content = b"  12.5 /home/test/a\n 100.0 /home/test/dir with spaces\n\n/home/test/no-score\n";
+
+        assert_eq!(
+            parse(content),
+            vec![
+                (12.5, PathBuf::from("/home/test/a")),
+                (100.0, PathBuf::from("/home/test/dir with spaces")),
+                (0.0, PathBuf::from("/home/test/no-score")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_keeps_non_utf8_paths() {
+        let content = b"1.5 /home/test/caf\xe9\n/home/test/\xff\n";
+
+        assert_eq!(
+            parse(content),
+            vec![
+                (1.5, PathBuf::from(OsStr::from_bytes(b"/home/test/caf\xe9"))),
+                (0.0, PathBuf::from(OsStr::from_bytes(b"/home/test/\xff"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn select_sorts_by_score_and_limits() {
+        let dirs = parse(b"1 /a\n3 /b\n2 /c\n");
+
+        assert_eq!(
+            select(dirs, None, Some(2)),
+            vec![PathBuf::from("/b"), PathBuf::from("/c")]
+        );
+    }
+
+    #[test]
+    fn select_filters_roots() {
+        let dirs = parse(b"1 /home/test/code/a\n3 /home/test/codex\n2 /tmp/c\n4 /home/test/code/b\n");
+        let roots = [PathBuf::from("/home/test/code"), PathBuf::from("/tmp")];
+
+        assert_eq!(
+            select(dirs, Some(&roots), None),
+            vec![
+                PathBuf::from("/home/test/code/b"),
+                PathBuf::from("/tmp/c"),
+                PathBuf::from("/home/test/code/a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_list_keeps_file_order() {
+        let dirs = parse(b"/b\n/a\n/c\n");
+
+        assert_eq!(
+            select(dirs, None, None),
+            vec![PathBuf::from("/b"), PathBuf::from("/a"), PathBuf::from("/c")]
+        );
+    }
+}
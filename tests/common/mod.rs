@@ -0,0 +1,163 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tmux_sessionizer::config::{Config, Entry, EntryPlain};
+use tmux_sessionizer::prompt::Picker;
+use tmux_sessionizer::prompt_item::PromptItem;
+use tmux_sessionizer::tmux::{Execute, Executor, Tmux};
+use tmux_sessionizer::Result;
+
+/// A tmux server on a socket in a temporary directory, killed on drop. Started with an empty config and a session
+/// named `base`.
+pub struct TestServer {
+    pub dir: tempfile::TempDir,
+    pub socket: PathBuf,
+    client: Option<Child>,
+}
+
+impl TestServer {
+    /// Returns `None` if tmux is not installed so tests can skip themselves.
+    pub fn start() -> Option<Self> {
+        if !Command::new("tmux")
+            .arg("-V")
+            .output()
+            .is_ok_and(|output| output.status.success())
+        {
+            eprintln!("tmux is not installed, skipping");
+            return None;
+        }
+        // Never talk to the server running the tests
+        std::env::remove_var("TMUX");
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("tmux.sock");
+        let server = Self {
+            socket,
+            dir,
+            client: None,
+        };
+        let base = server.dir.path().to_string_lossy().into_owned();
+        server.tmux(&["-f", "/dev/null", "new-session", "-d", "-s", "base", "-c", &base]);
+        Some(server)
+    }
+
+    /// Attaches a client on a pseudo terminal to the base session, which acts as the client switch-client switches.
+    pub fn attach_client(&mut self) {
+        let attach = format!("tmux -S '{}' attach -t base", self.socket.display());
+        let child = Command::new("script")
+            .args(["-qfc", &attach, "/dev/null"])
+            .env("TERM", "xterm")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        self.client = Some(child);
+        self.wait_for(|server| server.client_session().is_some());
+    }
+
+    pub fn tmux(&self, args: &[&str]) -> String {
+        let output = Command::new("tmux")
+            .arg("-S")
+            .arg(&self.socket)
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_owned()
+    }
+
+    pub fn sessions(&self) -> Vec<String> {
+        let mut sessions = self
+            .tmux(&["list-sessions", "-F", "#{session_name}"])
+            .lines()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        sessions.sort();
+        sessions
+    }
+
+    pub fn session_path(&self, session: &str) -> String {
+        self.tmux(&[
+            "display-message",
+            "-p",
+            "-t",
+            &format!("={session}:"),
+            "#{session_path}",
+        ])
+    }
+
+    pub fn client_session(&self) -> Option<String> {
+        Some(self.tmux(&["list-clients", "-F", "#{client_session}"])).filter(|session| !session.is_empty())
+    }
+
+    pub fn wait_for(&self, condition: impl Fn(&Self) -> bool) {
+        let start = Instant::now();
+        while !condition(self) {
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting for tmux");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    pub fn tmux_client(&self, inside_tmux: bool) -> Arc<Tmux<Executor>> {
+        let tmux = Tmux::new_with_executor(Executor)
+            .with_socket(&self.socket)
+            .with_inside_tmux(inside_tmux);
+        Arc::new(tmux)
+    }
+
+    /// Creates a directory in the server's temporary directory.
+    pub fn mkdir(&self, name: &str) -> PathBuf {
+        let path = self.dir.path().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.tmux(&["kill-server"]);
+        if let Some(ref mut client) = self.client {
+            let _ = client.kill();
+            let _ = client.wait();
+        }
+    }
+}
+
+pub fn config() -> Config {
+    Config {
+        hide_banner: true,
+        ..Default::default()
+    }
+}
+
+pub fn plain(name: &str, workdir: &Path) -> Entry {
+    Entry::Plain(EntryPlain {
+        name: name.to_owned(),
+        workdir: workdir.to_path_buf().into(),
+        preview_cmd: None,
+        window: false,
+    })
+}
+
+/// Picks the item with the given name, or nothing if it is `None`.
+pub struct ScriptedPicker(pub Option<&'static str>);
+
+impl Picker for ScriptedPicker {
+    fn pick<E, F>(&self, produce: F, _tmux: &Arc<Tmux<E>>, _config: &Config) -> Result<Option<PromptItem>>
+    where
+        E: Execute + Send + Sync + 'static,
+        F: FnOnce(&mut dyn FnMut(PromptItem) -> bool) -> Result<()> + Send,
+    {
+        let mut items = Vec::new();
+        produce(&mut |item| {
+            items.push(item);
+            true
+        })?;
+        Ok(self.0.and_then(|name| items.into_iter().find(|item| item.name == name)))
+    }
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{config, plain, ScriptedPicker, TestServer};
+use tmux_sessionizer::config::Command;
+use tmux_sessionizer::tmux::{ControlExecutor, SessionStats, Tmux};
+use tmux_sessionizer::{run, Error};
+
+#[test]
+fn creates_and_switches_to_new_session() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.attach_client();
+    let project = server.mkdir("project");
+
+    run(
+        vec![plain("project", &project)],
+        &server.tmux_client(true),
+        &config(),
+        &ScriptedPicker(Some("project")),
+    )
+    .unwrap();
+
+    assert_eq!(server.sessions(), vec!["base", "project"]);
+    assert_eq!(server.session_path("project"), project.to_string_lossy());
+    server.wait_for(|server| server.client_session().as_deref() == Some("project"));
+}
+
+#[test]
+fn switches_to_existing_session() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.attach_client();
+    let project = server.mkdir("project");
+    server.tmux(&["new-session", "-d", "-s", "other", "-c", "/"]);
+
+    run(
+        vec![plain("project", &project)],
+        &server.tmux_client(true),
+        &config(),
+        &ScriptedPicker(Some("other")),
+    )
+    .unwrap();
+
+    assert_eq!(server.sessions(), vec!["base", "other"]);
+    server.wait_for(|server| server.client_session().as_deref() == Some("other"));
+}
+
+#[test]
+fn eval_mode_only_creates_session() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.attach_client();
+    let project = server.mkdir("project");
+    let mut config = config();
+    config.eval_mode = true;
+
+    run(
+        vec![plain("project", &project)],
+        &server.tmux_client(true),
+        &config,
+        &ScriptedPicker(Some("project")),
+    )
+    .unwrap();
+
+    assert_eq!(server.sessions(), vec!["base", "project"]);
+    assert_eq!(server.client_session().as_deref(), Some("base"));
+}
+
+#[test]
+fn aborting_the_picker_is_an_error() {
+    let Some(server) = TestServer::start() else {
+        return;
+    };
+    let project = server.mkdir("project");
+
+    let res = run(
+        vec![plain("project", &project)],
+        &server.tmux_client(false),
+        &config(),
+        &ScriptedPicker(None),
+    );
+
+    assert!(matches!(res, Err(Error::Aborted)));
+    assert_eq!(server.sessions(), vec!["base"]);
+}
+
+#[test]
+fn grouped_session_shares_windows() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.attach_client();
+    let project = server.mkdir("project");
+    let mut config = config();
+    config.command = Some(Command::List {
+        grouped: true,
+        window: false,
+    });
+
+    run(
+        vec![plain("project", &project)],
+        &server.tmux_client(true),
+        &config,
+        &ScriptedPicker(Some("project")),
+    )
+    .unwrap();
+
+    let groups = server.tmux(&["list-sessions", "-F", "#{session_name} #{session_group}"]);
+    let grouped = groups
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, group)| *group == "project")
+        .map(|(name, _)| name.to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(grouped.len(), 2, "{groups}");
+    let client_session = grouped.into_iter().find(|name| name != "project").unwrap();
+    server.wait_for(|server| server.client_session().as_ref() == Some(&client_session));
+}
+
+#[test]
+fn opens_window_in_current_session_once() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.attach_client();
+    let project = server.mkdir("project");
+    let mut config = config();
+    config.command = Some(Command::List {
+        grouped: false,
+        window: true,
+    });
+    let windows = |server: &TestServer| server.tmux(&["list-windows", "-t", "base", "-F", "#{window_name}"]);
+
+    for _ in 0..2 {
+        run(
+            vec![plain("project", &project)],
+            &server.tmux_client(true),
+            &config,
+            &ScriptedPicker(Some("project")),
+        )
+        .unwrap();
+        server.wait_for(|server| windows(server).lines().count() == 2);
+    }
+
+    assert_eq!(server.sessions(), vec!["base"]);
+    assert_eq!(windows(&server).lines().last(), Some("project"));
+    let active = server.tmux(&[
+        "display-message",
+        "-p",
+        "-t",
+        "base",
+        "#{window_name} #{pane_current_path}",
+    ]);
+    assert_eq!(active.trim(), format!("project {}", project.display()));
+}
+
+#[test]
+fn kills_selected_session() {
+    let Some(server) = TestServer::start() else {
+        return;
+    };
+    server.tmux(&["new-session", "-d", "-s", "other", "-c", "/"]);
+    let mut config = config();
+    config.command = Some(Command::Kill {
+        current: false,
+        name: None,
+    });
+
+    run(
+        vec![],
+        &server.tmux_client(false),
+        &config,
+        &ScriptedPicker(Some("other")),
+    )
+    .unwrap();
+
+    assert_eq!(server.sessions(), vec!["base"]);
+}
+
+#[test]
+fn kills_session_by_name() {
+    let Some(server) = TestServer::start() else {
+        return;
+    };
+    server.tmux(&["new-session", "-d", "-s", "other", "-c", "/"]);
+    let mut config = config();
+    config.command = Some(Command::Kill {
+        current: false,
+        name: Some("other".to_owned()),
+    });
+
+    run(vec![], &server.tmux_client(false), &config, &ScriptedPicker(None)).unwrap();
+
+    assert_eq!(server.sessions(), vec!["base"]);
+}
+
+#[test]
+fn kills_current_session() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.tmux(&["new-session", "-d", "-s", "other", "-c", "/"]);
+    server.attach_client();
+    let mut config = config();
+    config.command = Some(Command::Kill {
+        current: true,
+        name: None,
+    });
+
+    run(vec![], &server.tmux_client(true), &config, &ScriptedPicker(None)).unwrap();
+
+    assert_eq!(server.sessions(), vec!["other"]);
+}
+
+#[test]
+fn chained_commands_keep_trailing_separators() {
+    let Some(server) = TestServer::start() else {
+        return;
+    };
+
+    server
+        .tmux_client(false)
+        .run_chain(&[
+            vec!["new-session", "-d", "-s", "a;"],
+            vec!["new-session", "-d", "-s", "b"],
+        ])
+        .unwrap();
+
+    assert_eq!(server.sessions(), vec!["a;", "b", "base"]);
+}
+
+#[test]
+fn control_mode_queries_match_spawned_queries() {
+    let Some(mut server) = TestServer::start() else {
+        return;
+    };
+    server.tmux(&["new-session", "-d", "-s", "other", "-c", "/"]);
+    server.attach_client();
+    let spawned = server.tmux_client(false);
+    let control = Tmux::new_with_executor(ControlExecutor::connect(Some(&server.socket)).unwrap())
+        .with_socket(&server.socket)
+        .with_inside_tmux(false);
+
+    let sessions = attached(control.get_active_sessions().unwrap().value());
+    assert_eq!(sessions, attached(spawned.get_active_sessions().unwrap().value()));
+    assert_eq!(sessions, vec![("base".to_owned(), true), ("other".to_owned(), false)]);
+
+    // Anything but queries runs in its own process
+    control.kill_session("other").unwrap();
+    assert_eq!(server.sessions(), vec!["base"]);
+}
+
+fn attached(sessions: HashMap<String, SessionStats>) -> Vec<(String, bool)> {
+    let mut sessions = sessions
+        .into_iter()
+        .map(|(name, stats)| (name, stats.attached))
+        .collect::<Vec<_>>();
+    sessions.sort();
+    sessions
+}